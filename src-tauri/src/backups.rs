@@ -0,0 +1,565 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rusqlite::{Connection, DatabaseName, OpenFlags};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::db::Database;
+use crate::images::ImageStore;
+use crate::{migrations, settings};
+
+/// Folder next to the library database that holds its snapshots.
+pub const BACKUP_DIR: &str = "backups";
+
+/// Settings key of the retention rules.
+const SETTINGS_KEY: &str = "backups";
+
+/// Upper bound on the number of snapshots kept, so a typo cannot fill the
+/// disk.
+const MAX_COUNT: usize = 1000;
+
+/// Snapshots of the library database, taken with SQLite's online backup
+/// API so they are consistent even while the app is using it. Each one is
+/// a complete database named `<unix millis>-<reason>.db`; the name without
+/// `.db` is its id. Managed as Tauri state.
+pub struct BackupStore {
+    dir: PathBuf,
+}
+
+/// How many snapshots to keep, and for how long. The newest one is never
+/// removed. Startup snapshots and the ones taken before a change are
+/// counted apart, so launching the app often cannot rotate out the
+/// snapshot taken before a deletion or edit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Retention {
+    pub max_count: usize,
+    pub max_age_days: u32,
+}
+
+impl Default for Retention {
+    fn default() -> Self {
+        Self {
+            max_count: 20,
+            max_age_days: 30,
+        }
+    }
+}
+
+impl Retention {
+    fn validate(&self) -> Result<(), String> {
+        if !(1..=MAX_COUNT).contains(&self.max_count) {
+            return Err(format!("Keep between 1 and {MAX_COUNT} backups"));
+        }
+        if self.max_age_days == 0 {
+            return Err("Backups must be kept for at least a day".into());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Backup {
+    pub id: String,
+    /// What the snapshot was taken before, e.g. `startup` or `delete-entry`.
+    pub reason: String,
+    /// Unix seconds.
+    pub created_at: i64,
+    /// Bytes on disk.
+    pub size: u64,
+}
+
+impl Backup {
+    /// The id's parts, or `None` for a file the store did not write.
+    fn parse(id: &str) -> Option<(i64, &str)> {
+        let (millis, reason) = id.split_once('-')?;
+        let millis = millis.parse().ok()?;
+        is_reason(reason).then_some((millis, reason))
+    }
+}
+
+/// Reason of the snapshot taken each time the app starts.
+pub const STARTUP: &str = "startup";
+
+/// Reasons are lowercase words joined by `-`, so ids are safe file names.
+fn is_reason(reason: &str) -> bool {
+    !reason.is_empty() && reason.chars().all(|c| c.is_ascii_lowercase() || c == '-')
+}
+
+impl BackupStore {
+    /// A store in `dir`, created on the first snapshot.
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    fn path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{id}.db"))
+    }
+
+    /// Snapshots, newest first. Files that do not look like one are
+    /// ignored.
+    pub fn list(&self) -> io::Result<Vec<Backup>> {
+        let dir = match fs::read_dir(&self.dir) {
+            Ok(dir) => dir,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        let mut backups = Vec::new();
+        for file in dir {
+            let file = file?;
+            let name = file.file_name();
+            let Some(id) = name.to_str().and_then(|n| n.strip_suffix(".db")) else {
+                continue;
+            };
+            let Some((millis, reason)) = Backup::parse(id) else {
+                continue;
+            };
+            backups.push((
+                millis,
+                Backup {
+                    id: id.to_string(),
+                    reason: reason.to_string(),
+                    created_at: millis / 1000,
+                    size: file.metadata()?.len(),
+                },
+            ));
+        }
+        backups.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| b.1.id.cmp(&a.1.id)));
+        Ok(backups.into_iter().map(|(_, backup)| backup).collect())
+    }
+
+    /// Copy the database into a new snapshot, then drop the ones the
+    /// retention rules no longer cover.
+    pub fn snapshot(&self, conn: &Connection, reason: &str) -> Result<Backup, String> {
+        let backup = self.write(conn, reason)?;
+        let retention = load_retention(conn).map_err(|e| e.to_string())?;
+        self.rotate(retention, backup.created_at)
+            .map_err(|e| format!("Could not remove old backups: {e}"))?;
+        Ok(backup)
+    }
+
+    /// Copy the database into a new snapshot. The copy is written next to
+    /// its final name and renamed into place, so a half-written file is
+    /// never listed.
+    fn write(&self, conn: &Connection, reason: &str) -> Result<Backup, String> {
+        if !is_reason(reason) {
+            return Err(format!("Invalid backup reason: {reason}"));
+        }
+        let failed = |e: &dyn std::fmt::Display| format!("Could not back up the library: {e}");
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as i64)
+            .unwrap_or_default();
+        // Two snapshots in the same millisecond must not share a file.
+        let id = (millis..)
+            .map(|m| format!("{m}-{reason}"))
+            .find(|id| !self.path(id).exists())
+            .expect("an unused id");
+
+        fs::create_dir_all(&self.dir).map_err(|e| failed(&e))?;
+        let partial = self.dir.join(format!("{id}.db.partial"));
+        fs::remove_file(&partial).ok();
+        conn.backup(DatabaseName::Main, &partial, None)
+            .map_err(|e| failed(&e))?;
+        fs::rename(&partial, self.path(&id)).map_err(|e| failed(&e))?;
+        let size = fs::metadata(self.path(&id)).map_err(|e| failed(&e))?.len();
+        Ok(Backup {
+            reason: reason.to_string(),
+            created_at: millis / 1000,
+            size,
+            id,
+        })
+    }
+
+    /// Remove snapshots past the count or age limit, keeping the newest.
+    /// Startup snapshots and the others each get `max_count`. Returns how
+    /// many were removed.
+    pub fn rotate(&self, retention: Retention, now: i64) -> io::Result<usize> {
+        let max_age = i64::from(retention.max_age_days) * 24 * 60 * 60;
+        let (mut startups, mut changes) = (0, 0);
+        let mut removed = 0;
+        for (i, backup) in self.list()?.into_iter().enumerate() {
+            let kept = if backup.reason == STARTUP {
+                &mut startups
+            } else {
+                &mut changes
+            };
+            *kept += 1;
+            if i > 0 && (*kept > retention.max_count || now - backup.created_at > max_age) {
+                fs::remove_file(self.path(&backup.id))?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Replace the live database with a snapshot, in place, so the shared
+    /// connection stays valid. The current state is snapshotted first, and
+    /// put back if the restored one cannot be migrated to this build's
+    /// schema. Old snapshots are only rotated out afterwards, so the one
+    /// being restored cannot be among them.
+    pub fn restore(
+        &self,
+        conn: &mut Connection,
+        images: &ImageStore,
+        id: &str,
+    ) -> Result<(), String> {
+        let path = self.path(id);
+        if Backup::parse(id).is_none() || !path.is_file() {
+            return Err(format!("Backup {id} not found"));
+        }
+        check(&path)?;
+
+        let safety = self.write(conn, "restore")?;
+        let restored = copy_into(conn, &path).and_then(|()| migrations::run(conn, images));
+        if let Err(e) = restored {
+            copy_into(conn, &self.path(&safety.id))?;
+            return Err(format!("Could not restore backup {id}: {e}"));
+        }
+        let retention = load_retention(conn).map_err(|e| e.to_string())?;
+        self.rotate(retention, safety.created_at)
+            .map_err(|e| format!("Could not remove old backups: {e}"))?;
+        Ok(())
+    }
+}
+
+/// Make sure a snapshot is an intact library this build can open, before
+/// anything is overwritten with it.
+fn check(path: &Path) -> Result<(), String> {
+    let damaged = |e: rusqlite::Error| format!("{} is damaged: {e}", path.display());
+    let conn =
+        Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY).map_err(damaged)?;
+    let status: String = conn
+        .query_row("PRAGMA quick_check", [], |row| row.get(0))
+        .map_err(damaged)?;
+    if status != "ok" {
+        return Err(format!("{} is damaged: {status}", path.display()));
+    }
+    let version: i64 = conn
+        .pragma_query_value(None, "user_version", |row| row.get(0))
+        .map_err(damaged)?;
+    if version > migrations::latest_version() {
+        return Err(format!(
+            "The backup was written by a newer version of the app (schema {version}, this build knows {})",
+            migrations::latest_version()
+        ));
+    }
+    Ok(())
+}
+
+fn copy_into(conn: &mut Connection, path: &Path) -> Result<(), String> {
+    conn.restore(
+        DatabaseName::Main,
+        path,
+        None::<fn(rusqlite::backup::Progress)>,
+    )
+    .map_err(|e| e.to_string())
+}
+
+pub fn load_retention(conn: &Connection) -> rusqlite::Result<Retention> {
+    Ok(settings::get(conn, SETTINGS_KEY)?.unwrap_or_default())
+}
+
+// --- COMMANDS ---
+
+#[tauri::command]
+pub fn list_backups(backups: State<'_, BackupStore>) -> Result<Vec<Backup>, String> {
+    backups.list().map_err(|e| e.to_string())
+}
+
+/// Swap the library for a snapshot while the app is running. The UI
+/// reloads its entries afterwards.
+#[tauri::command(async)]
+pub fn restore_backup(
+    db: State<'_, Database>,
+    images: State<'_, ImageStore>,
+    backups: State<'_, BackupStore>,
+    id: String,
+) -> Result<(), String> {
+    backups.restore(&mut db.conn(), &images, &id)
+}
+
+#[tauri::command]
+pub fn backup_settings(db: State<'_, Database>) -> Result<Retention, String> {
+    load_retention(&db.conn()).map_err(|e| e.to_string())
+}
+
+/// Save new retention rules and apply them right away.
+#[tauri::command]
+pub fn save_backup_settings(
+    db: State<'_, Database>,
+    backups: State<'_, BackupStore>,
+    settings: Retention,
+) -> Result<(), String> {
+    settings.validate()?;
+    settings::set(&db.conn(), SETTINGS_KEY, &settings).map_err(|e| e.to_string())?;
+    backups
+        .rotate(settings, crate::db::unix_now())
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A migrated library on disk with a backup store next to it, in a
+    /// fresh temporary folder. Removed again on drop.
+    struct Library {
+        dir: PathBuf,
+        conn: Connection,
+        images: ImageStore,
+        backups: BackupStore,
+    }
+
+    impl Library {
+        fn new(name: &str) -> Self {
+            let dir =
+                std::env::temp_dir().join(format!("kscope-backups-{}-{name}", std::process::id()));
+            fs::remove_dir_all(&dir).ok();
+            fs::create_dir_all(&dir).unwrap();
+            let images = ImageStore::new(dir.join("images"));
+            let mut conn = Connection::open(dir.join("kscope.db")).unwrap();
+            migrations::run(&mut conn, &images).unwrap();
+            let backups = BackupStore::new(dir.join(BACKUP_DIR));
+            Self {
+                dir,
+                conn,
+                images,
+                backups,
+            }
+        }
+
+        fn add(&self, name: &str) {
+            self.conn
+                .execute(
+                    "INSERT INTO entries (uuid, category_id, name, launch_type, launch_data)
+                     VALUES (?1, 1, ?1, 'exe', 'game.exe')",
+                    [name],
+                )
+                .unwrap();
+        }
+
+        fn names(&self) -> Vec<String> {
+            self.conn
+                .prepare("SELECT name FROM entries ORDER BY id")
+                .unwrap()
+                .query_map([], |row| row.get(0))
+                .unwrap()
+                .collect::<rusqlite::Result<_>>()
+                .unwrap()
+        }
+
+        /// A snapshot file with the given id holding an empty database.
+        fn fake(&self, id: &str) {
+            fs::create_dir_all(&self.backups.dir).unwrap();
+            Connection::open(self.backups.path(id)).unwrap();
+        }
+
+        fn ids(&self) -> Vec<String> {
+            self.backups
+                .list()
+                .unwrap()
+                .into_iter()
+                .map(|b| b.id)
+                .collect()
+        }
+    }
+
+    impl Drop for Library {
+        fn drop(&mut self) {
+            fs::remove_dir_all(&self.dir).ok();
+        }
+    }
+
+    #[test]
+    fn snapshot_and_restore() {
+        let mut library = Library::new("restore");
+        library.add("Portal");
+        let before = library
+            .backups
+            .snapshot(&library.conn, "delete-entry")
+            .unwrap();
+        assert_eq!(before.reason, "delete-entry");
+        assert!(before.size > 0);
+
+        library.conn.execute("DELETE FROM entries", []).unwrap();
+        library.add("Doom");
+        library
+            .backups
+            .restore(&mut library.conn, &library.images, &before.id)
+            .unwrap();
+        assert_eq!(library.names(), ["Portal"]);
+
+        // The state that was replaced can be restored in turn.
+        let backups = library.backups.list().unwrap();
+        assert_eq!(backups.len(), 2);
+        assert_eq!(backups[0].reason, "restore");
+        library
+            .backups
+            .restore(&mut library.conn, &library.images, &backups[0].id)
+            .unwrap();
+        assert_eq!(library.names(), ["Doom"]);
+    }
+
+    #[test]
+    fn restore_migrates_old_snapshots() {
+        let mut library = Library::new("old");
+        library.add("Portal");
+        fs::create_dir_all(&library.backups.dir).unwrap();
+        let fixture = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fixtures/db/launch_type.db");
+        fs::copy(fixture, library.backups.path("1-manual")).unwrap();
+
+        library
+            .backups
+            .restore(&mut library.conn, &library.images, "1-manual")
+            .unwrap();
+        assert!(!library.names().contains(&"Portal".to_string()));
+        let version: i64 = library
+            .conn
+            .pragma_query_value(None, "user_version", |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, migrations::latest_version());
+    }
+
+    #[test]
+    fn bad_snapshots_change_nothing() {
+        let mut library = Library::new("bad");
+        library.add("Portal");
+
+        library.fake("2-newer");
+        Connection::open(library.backups.path("2-newer"))
+            .unwrap()
+            .pragma_update(None, "user_version", migrations::latest_version() + 1)
+            .unwrap();
+        let error = library
+            .backups
+            .restore(&mut library.conn, &library.images, "2-newer")
+            .unwrap_err();
+        assert!(error.contains("newer version"), "{error}");
+
+        fs::write(library.backups.path("3-junk"), b"not a database").unwrap();
+        let error = library
+            .backups
+            .restore(&mut library.conn, &library.images, "3-junk")
+            .unwrap_err();
+        assert!(error.contains("damaged"), "{error}");
+
+        for id in ["missing-one", "../kscope", "4-"] {
+            let error = library
+                .backups
+                .restore(&mut library.conn, &library.images, id)
+                .unwrap_err();
+            assert_eq!(error, format!("Backup {id} not found"));
+        }
+
+        assert_eq!(library.names(), ["Portal"]);
+        assert_eq!(library.ids(), ["3-junk", "2-newer"]);
+    }
+
+    #[test]
+    fn rotates_by_count_and_age() {
+        let library = Library::new("rotate");
+        let day = 24 * 60 * 60 * 1000;
+        let now = 100 * day;
+        for days_ago in [0, 1, 2, 40, 50] {
+            library.fake(&format!("{}-startup", now - days_ago * day));
+        }
+        fs::write(library.backups.dir.join("notes.txt"), "kept").unwrap();
+
+        let retention = Retention {
+            max_count: 10,
+            max_age_days: 30,
+        };
+        assert_eq!(library.backups.rotate(retention, now / 1000).unwrap(), 2);
+        assert_eq!(library.ids().len(), 3);
+
+        let retention = Retention {
+            max_count: 2,
+            max_age_days: 30,
+        };
+        assert_eq!(library.backups.rotate(retention, now / 1000).unwrap(), 1);
+        assert_eq!(
+            library.ids(),
+            [format!("{now}-startup"), format!("{}-startup", now - day)]
+        );
+
+        // The newest snapshot is kept however old it is.
+        let later = (now + 365 * day) / 1000;
+        assert_eq!(library.backups.rotate(retention, later).unwrap(), 1);
+        assert_eq!(library.ids(), [format!("{now}-startup")]);
+        assert!(library.backups.dir.join("notes.txt").is_file());
+    }
+
+    #[test]
+    fn startups_do_not_rotate_out_snapshots_before_changes() {
+        let library = Library::new("kinds");
+        let now = 1_000_000;
+        library.fake(&format!("{}-delete-entry", now - 10));
+        library.fake(&format!("{}-edit-entry", now - 9));
+        for i in 0..5 {
+            library.fake(&format!("{}-startup", now - 5 + i));
+        }
+
+        let retention = Retention {
+            max_count: 2,
+            max_age_days: 30,
+        };
+        assert_eq!(library.backups.rotate(retention, now / 1000).unwrap(), 3);
+        assert_eq!(
+            library.ids(),
+            [
+                format!("{}-startup", now - 1),
+                format!("{}-startup", now - 2),
+                format!("{}-edit-entry", now - 9),
+                format!("{}-delete-entry", now - 10),
+            ]
+        );
+    }
+
+    #[test]
+    fn snapshot_applies_saved_retention() {
+        let mut library = Library::new("saved");
+        settings::set(
+            &library.conn,
+            SETTINGS_KEY,
+            &Retention {
+                max_count: 2,
+                max_age_days: 30,
+            },
+        )
+        .unwrap();
+        for _ in 0..3 {
+            library.backups.snapshot(&library.conn, STARTUP).unwrap();
+        }
+        assert_eq!(library.ids().len(), 2);
+        assert!(library
+            .backups
+            .snapshot(&library.conn, "Bad Reason")
+            .is_err());
+
+        // Restoring under the tightest limit still finds its snapshot.
+        settings::set(
+            &library.conn,
+            SETTINGS_KEY,
+            &Retention {
+                max_count: 1,
+                max_age_days: 30,
+            },
+        )
+        .unwrap();
+        let oldest = library.ids().pop().unwrap();
+        library
+            .backups
+            .restore(&mut library.conn, &library.images, &oldest)
+            .unwrap();
+        assert!(Retention {
+            max_count: 0,
+            max_age_days: 1
+        }
+        .validate()
+        .is_err());
+    }
+}
@@ -0,0 +1,447 @@
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::backups::BackupStore;
+use crate::db::Database;
+
+/// Categories are the launcher's tabs, listed by `sort_order`. Every entry
+/// belongs to exactly one; a category cannot be removed while it has any.
+/// A later migration adds the `entry_sort` column.
+pub const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS categories (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+        display_mode TEXT NOT NULL DEFAULT 'grid' CHECK(display_mode IN ('grid', 'list')),
+        sort_order INTEGER NOT NULL DEFAULT 0
+    );
+";
+
+/// The categories the old `game` and `app` types became, by type. Libraries
+/// start with these, and exports from before categories are read with them.
+pub const DEFAULTS: [(&str, &str, DisplayMode); 2] = [
+    ("game", "Games", DisplayMode::Grid),
+    ("app", "Apps", DisplayMode::List),
+];
+
+/// How a category's entries are shown. The names match the thumbnail kinds
+/// rendered for them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DisplayMode {
+    /// Cover cards with name, playtime and a play button.
+    Grid,
+    /// Compact icon tiles.
+    List,
+}
+
+impl DisplayMode {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Grid => "grid",
+            Self::List => "list",
+        }
+    }
+}
+
+impl ToSql for DisplayMode {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(self.as_str().into())
+    }
+}
+
+impl FromSql for DisplayMode {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        match value.as_str()? {
+            "grid" => Ok(Self::Grid),
+            "list" => Ok(Self::List),
+            other => Err(FromSqlError::Other(
+                format!("Unknown display mode: {other}").into(),
+            )),
+        }
+    }
+}
+
+/// The order of a category's entries.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntrySort {
+    /// Alphabetical, ignoring case.
+    Name,
+    /// Newest first.
+    #[default]
+    RecentlyAdded,
+    /// Most recently started first; never played last.
+    RecentlyPlayed,
+    /// Most time played first.
+    Playtime,
+}
+
+impl EntrySort {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Name => "name",
+            Self::RecentlyAdded => "recently_added",
+            Self::RecentlyPlayed => "recently_played",
+            Self::Playtime => "playtime",
+        }
+    }
+}
+
+impl ToSql for EntrySort {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(self.as_str().into())
+    }
+}
+
+impl FromSql for EntrySort {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        match value.as_str()? {
+            "name" => Ok(Self::Name),
+            "recently_added" => Ok(Self::RecentlyAdded),
+            "recently_played" => Ok(Self::RecentlyPlayed),
+            "playtime" => Ok(Self::Playtime),
+            other => Err(FromSqlError::Other(
+                format!("Unknown entry sort: {other}").into(),
+            )),
+        }
+    }
+}
+
+/// What the category editor saves. Exports from before entry sorts read as
+/// newest first.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CategoryFields {
+    pub name: String,
+    pub display_mode: DisplayMode,
+    #[serde(default)]
+    pub entry_sort: EntrySort,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Category {
+    pub id: i64,
+    #[serde(flatten)]
+    pub fields: CategoryFields,
+    pub sort_order: i64,
+}
+
+impl Category {
+    fn from_row(row: &Row<'_>) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get("id")?,
+            fields: CategoryFields {
+                name: row.get("name")?,
+                display_mode: row.get("display_mode")?,
+                entry_sort: row.get("entry_sort")?,
+            },
+            sort_order: row.get("sort_order")?,
+        })
+    }
+}
+
+const SELECT: &str = "SELECT id, name, display_mode, entry_sort, sort_order FROM categories";
+
+/// All categories, in tab order.
+pub fn list(conn: &Connection) -> rusqlite::Result<Vec<Category>> {
+    let mut stmt = conn.prepare(&format!("{SELECT} ORDER BY sort_order, id"))?;
+    let rows = stmt.query_map([], Category::from_row)?;
+    rows.collect()
+}
+
+pub fn get(conn: &Connection, id: i64) -> rusqlite::Result<Option<Category>> {
+    conn.query_row(
+        &format!("{SELECT} WHERE id = ?1"),
+        params![id],
+        Category::from_row,
+    )
+    .optional()
+}
+
+/// Look a category up by name, ignoring case.
+pub fn find(conn: &Connection, name: &str) -> rusqlite::Result<Option<Category>> {
+    conn.query_row(
+        &format!("{SELECT} WHERE name = ?1"),
+        params![name.trim()],
+        Category::from_row,
+    )
+    .optional()
+}
+
+/// Trim the name and make sure no other category has it.
+fn validate(
+    conn: &Connection,
+    id: Option<i64>,
+    fields: CategoryFields,
+) -> Result<CategoryFields, String> {
+    let name = fields.name.trim().to_string();
+    if name.is_empty() {
+        return Err("Category name cannot be empty".into());
+    }
+    let existing = find(conn, &name).map_err(|e| e.to_string())?;
+    if existing.is_some_and(|c| Some(c.id) != id) {
+        return Err(format!("A category named {name} already exists"));
+    }
+    Ok(CategoryFields { name, ..fields })
+}
+
+/// Validate and add a category after the existing ones.
+pub fn create(conn: &Connection, fields: CategoryFields) -> Result<Category, String> {
+    let fields = validate(conn, None, fields)?;
+    conn.execute(
+        "INSERT INTO categories (name, display_mode, entry_sort, sort_order)
+         VALUES (?1, ?2, ?3, (SELECT COALESCE(MAX(sort_order) + 1, 0) FROM categories))",
+        params![fields.name, fields.display_mode, fields.entry_sort],
+    )
+    .map_err(|e| e.to_string())?;
+    get(conn, conn.last_insert_rowid())
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Category vanished after insert".into())
+}
+
+/// Rename a category or change how its entries are shown and sorted. Its
+/// place in the order stays.
+pub fn update(conn: &Connection, id: i64, fields: CategoryFields) -> Result<Category, String> {
+    let fields = validate(conn, Some(id), fields)?;
+    let changed = conn
+        .execute(
+            "UPDATE categories SET name = ?2, display_mode = ?3, entry_sort = ?4 WHERE id = ?1",
+            params![id, fields.name, fields.display_mode, fields.entry_sort],
+        )
+        .map_err(|e| e.to_string())?;
+    if changed == 0 {
+        return Err(not_found(id));
+    }
+    get(conn, id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| not_found(id))
+}
+
+/// Remove an empty category. The last one is kept, so new entries always
+/// have somewhere to go.
+pub fn delete(conn: &Connection, id: i64) -> Result<(), String> {
+    check_delete(conn, id)?;
+    conn.execute("DELETE FROM categories WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Why [`delete`] would refuse the category, without removing it.
+pub fn check_delete(conn: &Connection, id: i64) -> Result<(), String> {
+    let category = get(conn, id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| not_found(id))?;
+    let entries: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM entries WHERE category_id = ?1",
+            params![id],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    if entries > 0 {
+        return Err(format!(
+            "{} still has {entries} entries; move or delete them first",
+            category.fields.name
+        ));
+    }
+    let total: i64 = conn
+        .query_row("SELECT COUNT(*) FROM categories", [], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    if total == 1 {
+        return Err("The last category cannot be deleted".into());
+    }
+    Ok(())
+}
+
+/// Put the categories in the given order. Every category must be listed
+/// exactly once.
+pub fn reorder(conn: &mut Connection, ids: &[i64]) -> Result<(), String> {
+    let mut expected: Vec<i64> = list(conn)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|c| c.id)
+        .collect();
+    let mut given = ids.to_vec();
+    expected.sort_unstable();
+    given.sort_unstable();
+    if given != expected {
+        return Err("The new order must list every category once".into());
+    }
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    write_order(&tx, ids).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// Number the given categories from 0 in order. Others keep their number.
+pub fn write_order(conn: &Connection, ids: &[i64]) -> rusqlite::Result<()> {
+    for (position, id) in ids.iter().enumerate() {
+        conn.execute(
+            "UPDATE categories SET sort_order = ?2 WHERE id = ?1",
+            params![id, position as i64],
+        )?;
+    }
+    Ok(())
+}
+
+pub fn not_found(id: i64) -> String {
+    format!("Category {id} not found")
+}
+
+// --- COMMANDS ---
+
+#[tauri::command]
+pub fn list_categories(db: State<'_, Database>) -> Result<Vec<Category>, String> {
+    list(&db.conn()).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn create_category(
+    db: State<'_, Database>,
+    category: CategoryFields,
+) -> Result<Category, String> {
+    create(&db.conn(), category)
+}
+
+#[tauri::command]
+pub fn update_category(
+    db: State<'_, Database>,
+    id: i64,
+    category: CategoryFields,
+) -> Result<Category, String> {
+    update(&db.conn(), id, category)
+}
+
+#[tauri::command]
+pub fn delete_category(
+    db: State<'_, Database>,
+    backups: State<'_, BackupStore>,
+    id: i64,
+) -> Result<(), String> {
+    let conn = db.conn();
+    // A refused delete changes nothing, so it should not rotate out a
+    // snapshot.
+    check_delete(&conn, id)?;
+    backups.snapshot(&conn, "delete-category")?;
+    delete(&conn, id)
+}
+
+/// Save the tab order, given as category ids from first to last.
+#[tauri::command]
+pub fn reorder_categories(db: State<'_, Database>, ids: Vec<i64>) -> Result<(), String> {
+    reorder(&mut db.conn(), &ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::images::ImageStore;
+    use crate::migrations;
+
+    fn conn() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        let images = ImageStore::new(std::env::temp_dir().join("kscope-categories-no-images"));
+        migrations::run(&mut conn, &images).unwrap();
+        conn
+    }
+
+    fn fields(name: &str, display_mode: DisplayMode) -> CategoryFields {
+        CategoryFields {
+            name: name.into(),
+            display_mode,
+            entry_sort: EntrySort::default(),
+        }
+    }
+
+    fn names(conn: &Connection) -> Vec<String> {
+        list(conn)
+            .unwrap()
+            .into_iter()
+            .map(|c| c.fields.name)
+            .collect()
+    }
+
+    #[test]
+    fn libraries_start_with_games_and_apps() {
+        let conn = conn();
+        let categories = list(&conn).unwrap();
+        assert_eq!(names(&conn), ["Games", "Apps"]);
+        assert_eq!(categories[0].fields.display_mode, DisplayMode::Grid);
+        assert_eq!(categories[1].fields.display_mode, DisplayMode::List);
+    }
+
+    #[test]
+    fn create_update_and_reorder() {
+        let mut conn = conn();
+        let emulators = create(&conn, fields("  Emulators ", DisplayMode::Grid)).unwrap();
+        assert_eq!(emulators.fields.name, "Emulators");
+        assert_eq!(emulators.sort_order, 2);
+
+        let renamed = update(&conn, emulators.id, fields("Retro", DisplayMode::List)).unwrap();
+        assert_eq!(renamed.fields, fields("Retro", DisplayMode::List));
+        assert_eq!(renamed.sort_order, 2);
+        // Changing only the case of its own name is fine.
+        update(&conn, emulators.id, fields("RETRO", DisplayMode::List)).unwrap();
+
+        let ids: Vec<i64> = list(&conn).unwrap().iter().map(|c| c.id).collect();
+        reorder(&mut conn, &[ids[2], ids[0], ids[1]]).unwrap();
+        assert_eq!(names(&conn), ["RETRO", "Games", "Apps"]);
+        assert_eq!(find(&conn, "games").unwrap().unwrap().id, ids[0]);
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        let mut conn = conn();
+        assert_eq!(
+            create(&conn, fields(" ", DisplayMode::Grid)).unwrap_err(),
+            "Category name cannot be empty"
+        );
+        assert_eq!(
+            create(&conn, fields("games", DisplayMode::Grid)).unwrap_err(),
+            "A category named games already exists"
+        );
+        let apps = find(&conn, "Apps").unwrap().unwrap();
+        assert!(update(&conn, apps.id, fields("Games", DisplayMode::List)).is_err());
+        assert_eq!(
+            update(&conn, 99, fields("Work", DisplayMode::List)).unwrap_err(),
+            "Category 99 not found"
+        );
+
+        let ids: Vec<i64> = list(&conn).unwrap().iter().map(|c| c.id).collect();
+        for order in [vec![ids[0]], vec![ids[0], ids[0]], vec![ids[0], 99]] {
+            assert!(reorder(&mut conn, &order).is_err());
+        }
+        assert_eq!(names(&conn), ["Games", "Apps"]);
+    }
+
+    #[test]
+    fn only_empty_categories_are_deleted() {
+        let conn = conn();
+        let games = find(&conn, "Games").unwrap().unwrap();
+        let apps = find(&conn, "Apps").unwrap().unwrap();
+        conn.execute(
+            "INSERT INTO entries (uuid, category_id, name, launch_type, launch_data)
+             VALUES ('a', ?1, 'Portal', 'steam', '400')",
+            params![games.id],
+        )
+        .unwrap();
+
+        let error = delete(&conn, games.id).unwrap_err();
+        assert_eq!(
+            error,
+            "Games still has 1 entries; move or delete them first"
+        );
+        assert_eq!(check_delete(&conn, games.id), Err(error));
+        assert_eq!(check_delete(&conn, 99), Err(not_found(99)));
+        check_delete(&conn, apps.id).unwrap();
+        assert_eq!(names(&conn), ["Games", "Apps"]);
+        delete(&conn, apps.id).unwrap();
+        assert_eq!(names(&conn), ["Games"]);
+
+        conn.execute("DELETE FROM entries", []).unwrap();
+        assert_eq!(
+            delete(&conn, games.id).unwrap_err(),
+            "The last category cannot be deleted"
+        );
+    }
+}
@@ -0,0 +1,59 @@
+use std::fs;
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rusqlite::types::Type;
+use rusqlite::{Connection, Row};
+use serde::de::DeserializeOwned;
+
+use crate::images::ImageStore;
+use crate::migrations;
+
+/// File name of the library database, in the app config directory.
+pub const DB_FILE: &str = "kscope.db";
+
+/// Shared connection to the library database, managed as Tauri state.
+pub struct Database {
+    conn: Mutex<Connection>,
+}
+
+impl Database {
+    /// Open (or create) the database at `dir/kscope.db` and migrate it to
+    /// the current schema. Art found in old libraries is moved to `images`.
+    pub fn open(dir: &Path, images: &ImageStore) -> Result<Self, String> {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        let mut conn = Connection::open(dir.join(DB_FILE)).map_err(|e| e.to_string())?;
+        migrations::run(&mut conn, images)?;
+        Ok(Self::from_connection(conn))
+    }
+
+    pub fn from_connection(conn: Connection) -> Self {
+        Self {
+            conn: Mutex::new(conn),
+        }
+    }
+
+    /// Lock the connection. A poisoned lock is recovered, since a panic in
+    /// another command does not leave SQLite itself in a bad state.
+    pub fn conn(&self) -> MutexGuard<'_, Connection> {
+        self.conn.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Seconds since the Unix epoch, matching SQLite's `unixepoch()`.
+pub fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+/// Read a TEXT column holding JSON (used for maps and lists stored on a row).
+pub fn json_column<T: DeserializeOwned>(row: &Row<'_>, column: &str) -> rusqlite::Result<T> {
+    let text: String = row.get(column)?;
+    serde_json::from_str(&text).map_err(|e| {
+        let index = row.as_ref().column_index(column).unwrap_or_default();
+        rusqlite::Error::FromSqlConversionFailure(index, Type::Text, Box::new(e))
+    })
+}
@@ -0,0 +1,701 @@
+use std::collections::BTreeMap;
+
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use uuid::Uuid;
+
+use crate::backups::BackupStore;
+use crate::categories;
+use crate::db::{json_column, Database};
+use crate::health::validate_url;
+use crate::images::{self, ImageStore};
+use crate::launcher::hooks::{hook_command, Hooks};
+use crate::launcher::macros::{self, Macro};
+use crate::launcher::platform::LaunchCommand;
+use crate::launcher::runners::{self, Runner};
+use crate::launcher::{args, LaunchError, LaunchType};
+
+/// What the add / edit form saves. Everything but the name, target and
+/// image is optional.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EntryFields {
+    pub name: String,
+    pub launch_type: LaunchType,
+    /// Steam App ID, executable path, URL or script path.
+    pub launch_data: String,
+    #[serde(default)]
+    pub launch_args: String,
+    /// Empty means "the folder the executable or script lives in".
+    #[serde(default)]
+    pub working_dir: String,
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    #[serde(default)]
+    pub hooks: Hooks,
+    #[serde(default)]
+    pub single_instance: bool,
+    #[serde(default)]
+    pub start_steam_silently: bool,
+    /// `None` uses the global runner chain.
+    #[serde(default)]
+    pub runners: Option<Vec<Runner>>,
+    /// Cover art or icon in the image store. Empty for an entry without
+    /// art, such as one whose image could not be migrated; stored as NULL.
+    #[serde(default)]
+    pub image_hash: String,
+}
+
+/// An `entries` row.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Entry {
+    pub id: i64,
+    /// Identifies the entry across machines; see `library`.
+    pub uuid: String,
+    /// The tab it is listed under.
+    pub category_id: i64,
+    #[serde(flatten)]
+    pub fields: EntryFields,
+    pub deprecated: bool,
+    pub created_at: i64,
+}
+
+impl EntryFields {
+    /// Trim the text fields and check that the entry can be launched the
+    /// way the launcher will read it.
+    pub fn validate(mut self) -> Result<Self, String> {
+        self.name = self.name.trim().to_string();
+        self.launch_data = self.launch_data.trim().to_string();
+        self.launch_args = self.launch_args.trim().to_string();
+        self.working_dir = self.working_dir.trim().to_string();
+
+        if self.name.is_empty() {
+            return Err("Name cannot be empty".into());
+        }
+        if self.launch_data.is_empty() {
+            let missing = match self.launch_type {
+                LaunchType::Steam => Some("Steam App ID cannot be empty"),
+                LaunchType::Exe => Some("Executable path cannot be empty"),
+                LaunchType::Url => Some("URL cannot be empty"),
+                LaunchType::Bat => Some("Script path cannot be empty"),
+                // A macro has no target of its own; it runs its steps.
+                LaunchType::Macro => None,
+            };
+            if let Some(missing) = missing {
+                return Err(missing.into());
+            }
+        }
+        match self.launch_type {
+            LaunchType::Steam if !self.launch_data.bytes().all(|b| b.is_ascii_digit()) => {
+                return Err(format!(
+                    "Steam App ID must be a number, not \"{}\"",
+                    self.launch_data
+                ));
+            }
+            LaunchType::Url => {
+                validate_url(&self.launch_data).map_err(|e| format!("Invalid URL: {e}"))?;
+            }
+            _ => {}
+        }
+        args::split(&self.launch_args).map_err(|e| LaunchError::from(e).to_string())?;
+
+        check_env(&self.env)?;
+        for runner in self.runners.iter().flatten() {
+            check_env(&runner.env)?;
+        }
+        let context = LaunchCommand::new("target");
+        for hook in self.hooks.pre_launch.iter().chain(&self.hooks.post_exit) {
+            hook_command(hook, &context).map_err(|e| e.to_string())?;
+            if hook.timeout_secs == 0 {
+                return Err(format!("Hook `{}` needs a timeout", hook.command));
+            }
+        }
+        if let Some(chain) = &self.runners {
+            runners::wrap(context, chain).map_err(|e| e.to_string())?;
+        }
+
+        if !self.image_hash.is_empty() && !images::is_hash(&self.image_hash) {
+            return Err(format!("Not an image hash: {}", self.image_hash));
+        }
+        Ok(self)
+    }
+}
+
+/// Reject env names a process could not be given, such as `A=B` or an
+/// empty one. Entries, their runners and profiles check with this.
+pub fn check_env(env: &BTreeMap<String, String>) -> Result<(), String> {
+    match env.keys().find(|key| !is_env_name(key)) {
+        Some(key) => Err(format!("Invalid environment variable name: \"{key}\"")),
+        None => Ok(()),
+    }
+}
+
+fn is_env_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+// --- STORAGE ---
+
+const SELECT: &str = "
+    SELECT id, uuid, category_id, name, launch_type, launch_data,
+           COALESCE(launch_args, '') AS launch_args,
+           COALESCE(working_dir, '') AS working_dir,
+           COALESCE(env, '{}') AS env,
+           COALESCE(hooks, '{}') AS hooks,
+           COALESCE(single_instance, 0) AS single_instance,
+           COALESCE(start_steam_silently, 0) AS start_steam_silently,
+           COALESCE(runners, 'null') AS runners,
+           COALESCE(image_hash, '') AS image_hash,
+           COALESCE(deprecated, 0) AS deprecated,
+           COALESCE(created_at, 0) AS created_at
+    FROM entries";
+
+impl Entry {
+    fn from_row(row: &Row<'_>) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get("id")?,
+            uuid: row.get("uuid")?,
+            category_id: row.get("category_id")?,
+            fields: EntryFields {
+                name: row.get("name")?,
+                launch_type: row.get("launch_type")?,
+                launch_data: row.get("launch_data")?,
+                launch_args: row.get("launch_args")?,
+                working_dir: row.get("working_dir")?,
+                env: json_column(row, "env")?,
+                hooks: json_column(row, "hooks")?,
+                single_instance: row.get("single_instance")?,
+                start_steam_silently: row.get("start_steam_silently")?,
+                runners: json_column(row, "runners")?,
+                image_hash: row.get("image_hash")?,
+            },
+            deprecated: row.get("deprecated")?,
+            created_at: row.get("created_at")?,
+        })
+    }
+}
+
+/// Entries of one category, or of all in tab order, each category's in the
+/// order its `entry_sort` picks. Ties show the newest first.
+pub fn list(conn: &Connection, category_id: Option<i64>) -> rusqlite::Result<Vec<Entry>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT e.* FROM ({SELECT}) e JOIN categories c ON c.id = e.category_id
+         WHERE ?1 IS NULL OR e.category_id = ?1
+         ORDER BY c.sort_order, c.id,
+                  CASE c.entry_sort WHEN 'name' THEN e.name END COLLATE NOCASE,
+                  CASE c.entry_sort WHEN 'recently_played' THEN
+                      (SELECT MAX(started_at) FROM sessions WHERE entry_id = e.id)
+                  END DESC,
+                  CASE c.entry_sort WHEN 'playtime' THEN
+                      (SELECT SUM(COALESCE(ended_at, last_seen_at) - started_at)
+                       FROM sessions WHERE entry_id = e.id)
+                  END DESC,
+                  e.created_at DESC, e.id DESC"
+    ))?;
+    let rows = stmt.query_map(params![category_id], Entry::from_row)?;
+    rows.collect()
+}
+
+pub fn get(conn: &Connection, id: i64) -> rusqlite::Result<Option<Entry>> {
+    conn.query_row(
+        &format!("{SELECT} WHERE id = ?1"),
+        params![id],
+        Entry::from_row,
+    )
+    .optional()
+}
+
+/// Validate and insert a new entry under a fresh UUID.
+pub fn create(conn: &Connection, category_id: i64, fields: EntryFields) -> Result<Entry, String> {
+    let fields = fields.validate()?;
+    check_category(conn, category_id)?;
+    conn.execute(
+        "INSERT INTO entries (uuid, category_id, name, launch_type, launch_data, launch_args, working_dir,
+                              env, hooks, single_instance, start_steam_silently, runners, image_hash)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, NULLIF(?13, ''))",
+        params![
+            Uuid::new_v4().to_string(),
+            category_id,
+            fields.name,
+            fields.launch_type,
+            fields.launch_data,
+            fields.launch_args,
+            fields.working_dir,
+            to_json(&fields.env)?,
+            to_json(&fields.hooks)?,
+            fields.single_instance,
+            fields.start_steam_silently,
+            fields.runners.as_ref().map(to_json).transpose()?,
+            fields.image_hash,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    let id = conn.last_insert_rowid();
+    get(conn, id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| not_found(id))
+}
+
+/// Validate and replace the editable fields of an entry, moving it to
+/// `category_id`. Its deprecated flag and creation time are kept. An entry
+/// that stops being a macro loses its steps; one that is a step of a macro
+/// cannot become a macro itself.
+pub fn update(
+    conn: &Connection,
+    id: i64,
+    category_id: i64,
+    fields: EntryFields,
+) -> Result<Entry, String> {
+    let fields = fields.validate()?;
+    check_category(conn, category_id)?;
+    if fields.launch_type == LaunchType::Macro {
+        let is_step: bool = conn
+            .query_row(
+                "SELECT EXISTS (SELECT 1 FROM macro_steps WHERE entry_id = ?1)",
+                params![id],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+        if is_step {
+            return Err(format!(
+                "{} is a step of a macro and cannot become a macro itself",
+                fields.name
+            ));
+        }
+    } else {
+        macros::remove(conn, id).map_err(|e| e.to_string())?;
+    }
+    let changed = conn
+        .execute(
+            "UPDATE entries SET name = ?1, launch_type = ?2, launch_data = ?3, launch_args = ?4,
+                                working_dir = ?5, env = ?6, hooks = ?7, single_instance = ?8,
+                                start_steam_silently = ?9, runners = ?10, image_hash = NULLIF(?11, ''),
+                                category_id = ?12
+             WHERE id = ?13",
+            params![
+                fields.name,
+                fields.launch_type,
+                fields.launch_data,
+                fields.launch_args,
+                fields.working_dir,
+                to_json(&fields.env)?,
+                to_json(&fields.hooks)?,
+                fields.single_instance,
+                fields.start_steam_silently,
+                fields.runners.as_ref().map(to_json).transpose()?,
+                fields.image_hash,
+                category_id,
+                id,
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+    if changed == 0 {
+        return Err(not_found(id));
+    }
+    get(conn, id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| not_found(id))
+}
+
+/// Remove an entry. Its sessions, profiles, macro steps and health records
+/// go with it.
+pub fn delete(conn: &Connection, id: i64) -> Result<(), String> {
+    let removed = conn
+        .execute("DELETE FROM entries WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    if removed == 0 {
+        return Err(not_found(id));
+    }
+    Ok(())
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<String, String> {
+    serde_json::to_string(value).map_err(|e| e.to_string())
+}
+
+fn not_found(id: i64) -> String {
+    format!("Entry {id} not found")
+}
+
+fn check_category(conn: &Connection, id: i64) -> Result<(), String> {
+    categories::get(conn, id)
+        .map_err(|e| e.to_string())?
+        .map(|_| ())
+        .ok_or_else(|| categories::not_found(id))
+}
+
+/// The art must have been stored with `store_image` first.
+fn check_image(images: &ImageStore, entry: &EntryFields) -> Result<(), String> {
+    if !entry.image_hash.is_empty() && !images.contains(&entry.image_hash) {
+        return Err(
+            "The selected image is missing from the image store; please pick it again".into(),
+        );
+    }
+    Ok(())
+}
+
+// --- COMMANDS ---
+
+#[tauri::command]
+pub fn list_entries(
+    db: State<'_, Database>,
+    category_id: Option<i64>,
+) -> Result<Vec<Entry>, String> {
+    list(&db.conn(), category_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_entry(db: State<'_, Database>, id: i64) -> Result<Entry, String> {
+    get(&db.conn(), id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| not_found(id))
+}
+
+/// Store the steps of a macro entry in the transaction that saved it.
+/// Other entries need no `definition`.
+fn save_macro(conn: &Connection, entry: &Entry, definition: Option<Macro>) -> Result<(), String> {
+    if entry.fields.launch_type != LaunchType::Macro {
+        return Ok(());
+    }
+    let definition = definition.ok_or("A macro needs at least one step")?;
+    macros::save(conn, entry.id, &definition)
+}
+
+#[tauri::command]
+pub fn create_entry(
+    db: State<'_, Database>,
+    images: State<'_, ImageStore>,
+    category_id: i64,
+    entry: EntryFields,
+    definition: Option<Macro>,
+) -> Result<Entry, String> {
+    check_image(&images, &entry)?;
+    let mut conn = db.conn();
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let created = create(&tx, category_id, entry)?;
+    save_macro(&tx, &created, definition)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(created)
+}
+
+/// Save an edit, snapshotting the library first: an edit replaces every
+/// field and, for a macro, all of its steps.
+#[tauri::command]
+pub fn update_entry(
+    db: State<'_, Database>,
+    images: State<'_, ImageStore>,
+    backups: State<'_, BackupStore>,
+    id: i64,
+    category_id: i64,
+    entry: EntryFields,
+    definition: Option<Macro>,
+) -> Result<Entry, String> {
+    check_image(&images, &entry)?;
+    let mut conn = db.conn();
+    backups.snapshot(&conn, "edit-entry")?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let updated = update(&tx, id, category_id, entry)?;
+    save_macro(&tx, &updated, definition)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(updated)
+}
+
+#[tauri::command]
+pub fn delete_entry(
+    db: State<'_, Database>,
+    backups: State<'_, BackupStore>,
+    id: i64,
+) -> Result<(), String> {
+    let conn = db.conn();
+    backups.snapshot(&conn, "delete-entry")?;
+    delete(&conn, id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::launcher::hooks::Hook;
+    use crate::migrations;
+
+    /// The default categories of a fresh library.
+    const GAMES: i64 = 1;
+    const APPS: i64 = 2;
+
+    fn conn() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        let images = ImageStore::new(std::env::temp_dir().join("kscope-entries-no-images"));
+        migrations::run(&mut conn, &images).unwrap();
+        conn
+    }
+
+    fn fields(name: &str, launch_type: LaunchType, launch_data: &str) -> EntryFields {
+        EntryFields {
+            name: name.into(),
+            launch_type,
+            launch_data: launch_data.into(),
+            launch_args: String::new(),
+            working_dir: String::new(),
+            env: BTreeMap::new(),
+            hooks: Hooks::default(),
+            single_instance: false,
+            start_steam_silently: false,
+            runners: None,
+            image_hash: images::hash(b"cover"),
+        }
+    }
+
+    #[test]
+    fn create_round_trips_every_field() {
+        let conn = conn();
+        let mut portal = fields("  Portal  ", LaunchType::Exe, "~/Games/portal.exe");
+        portal.launch_args = r#"-novid +map "test chamber""#.into();
+        portal.working_dir = "~/Games".into();
+        portal.env = BTreeMap::from([("DXVK_HUD".into(), "fps".into())]);
+        portal.hooks.pre_launch.push(Hook {
+            command: "mount-games".into(),
+            timeout_secs: 5,
+        });
+        portal.single_instance = true;
+        portal.runners = Some(vec![Runner {
+            command: "wine".into(),
+            env: BTreeMap::from([("WINEPREFIX".into(), "~/.wine".into())]),
+        }]);
+
+        let created = create(&conn, GAMES, portal.clone()).unwrap();
+        assert_eq!(created.fields.name, "Portal");
+        assert_eq!(created.category_id, GAMES);
+        assert!(!created.deprecated);
+        assert_eq!(
+            created.fields,
+            EntryFields {
+                name: "Portal".into(),
+                ..portal
+            }
+        );
+        assert_eq!(get(&conn, created.id).unwrap(), Some(created));
+    }
+
+    #[test]
+    fn list_filters_by_category_newest_first() {
+        let conn = conn();
+        let steam = create(&conn, GAMES, fields("Portal 2", LaunchType::Steam, "620")).unwrap();
+        let wiki = create(
+            &conn,
+            APPS,
+            fields("Wiki", LaunchType::Url, "https://theportalwiki.com"),
+        )
+        .unwrap();
+        let doom = create(&conn, GAMES, fields("Doom", LaunchType::Bat, "doom.sh")).unwrap();
+
+        let ids = |entries: Vec<Entry>| entries.iter().map(|e| e.id).collect::<Vec<_>>();
+        assert_eq!(ids(list(&conn, Some(GAMES)).unwrap()), [doom.id, steam.id]);
+        assert_eq!(ids(list(&conn, Some(APPS)).unwrap()), [wiki.id]);
+        assert_eq!(list(&conn, None).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn list_follows_each_category_sort() {
+        let mut conn = conn();
+        let portal = create(&conn, GAMES, fields("portal", LaunchType::Steam, "400")).unwrap();
+        let doom = create(&conn, GAMES, fields("Doom", LaunchType::Bat, "doom.sh")).unwrap();
+        let quake = create(&conn, GAMES, fields("Quake", LaunchType::Bat, "quake.sh")).unwrap();
+        let wiki = create(&conn, APPS, fields("Wiki", LaunchType::Url, "https://wiki")).unwrap();
+        // Portal was played longest, Doom most recently, Quake never.
+        for (entry_id, started_at, ended_at) in [(portal.id, 100, 900), (doom.id, 1000, 1100)] {
+            conn.execute(
+                "INSERT INTO sessions (entry_id, started_at, ended_at, last_seen_at)
+                 VALUES (?1, ?2, ?3, ?3)",
+                params![entry_id, started_at, ended_at],
+            )
+            .unwrap();
+        }
+
+        let ids = |conn: &Connection| {
+            list(conn, Some(GAMES))
+                .unwrap()
+                .iter()
+                .map(|e| e.id)
+                .collect::<Vec<_>>()
+        };
+        let sort_games = |entry_sort| {
+            let games = categories::get(&conn, GAMES).unwrap().unwrap();
+            let fields = categories::CategoryFields {
+                entry_sort,
+                ..games.fields
+            };
+            categories::update(&conn, GAMES, fields).unwrap();
+        };
+        assert_eq!(ids(&conn), [quake.id, doom.id, portal.id]);
+        sort_games(categories::EntrySort::Name);
+        assert_eq!(ids(&conn), [doom.id, portal.id, quake.id]);
+        sort_games(categories::EntrySort::RecentlyPlayed);
+        assert_eq!(ids(&conn), [doom.id, portal.id, quake.id]);
+        sort_games(categories::EntrySort::Playtime);
+        assert_eq!(ids(&conn), [portal.id, doom.id, quake.id]);
+
+        // All entries come grouped by category, in tab order.
+        categories::reorder(&mut conn, &[APPS, GAMES]).unwrap();
+        let all: Vec<i64> = list(&conn, None).unwrap().iter().map(|e| e.id).collect();
+        assert_eq!(all, [wiki.id, portal.id, doom.id, quake.id]);
+    }
+
+    #[test]
+    fn update_moves_and_keeps_creation() {
+        let conn = conn();
+        let entry = create(&conn, APPS, fields("Notes", LaunchType::Exe, "notes")).unwrap();
+        conn.execute(
+            "UPDATE entries SET deprecated = 1 WHERE id = ?1",
+            params![entry.id],
+        )
+        .unwrap();
+
+        let mut changed = fields("Notes", LaunchType::Url, "https://notes.example");
+        changed.runners = Some(Vec::new());
+        let updated = update(&conn, entry.id, GAMES, changed).unwrap();
+        assert_eq!(updated.category_id, GAMES);
+        assert_eq!(updated.fields.launch_type, LaunchType::Url);
+        assert_eq!(updated.fields.runners, Some(Vec::new()));
+        assert!(updated.deprecated);
+        assert_eq!(updated.created_at, entry.created_at);
+
+        // Art is optional; none is stored as NULL.
+        let mut bare = fields("Notes", LaunchType::Url, "https://notes.example");
+        bare.image_hash = String::new();
+        let updated = update(&conn, entry.id, GAMES, bare).unwrap();
+        assert_eq!(updated.fields.image_hash, "");
+        let stored: Option<String> = conn
+            .query_row(
+                "SELECT image_hash FROM entries WHERE id = ?1",
+                params![entry.id],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(stored, None);
+
+        assert_eq!(
+            update(&conn, 999, APPS, fields("x", LaunchType::Exe, "x")).unwrap_err(),
+            "Entry 999 not found"
+        );
+        assert_eq!(
+            update(&conn, entry.id, 99, fields("x", LaunchType::Exe, "x")).unwrap_err(),
+            "Category 99 not found"
+        );
+    }
+
+    #[test]
+    fn delete_removes_dependent_rows() {
+        let conn = conn();
+        let entry = create(&conn, GAMES, fields("Portal 2", LaunchType::Steam, "620")).unwrap();
+        conn.execute(
+            "INSERT INTO sessions (entry_id, started_at, last_seen_at) VALUES (?1, 1, 1)",
+            params![entry.id],
+        )
+        .unwrap();
+
+        delete(&conn, entry.id).unwrap();
+        assert_eq!(get(&conn, entry.id).unwrap(), None);
+        let sessions: i64 = conn
+            .query_row("SELECT COUNT(*) FROM sessions", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(sessions, 0);
+        assert!(delete(&conn, entry.id).is_err());
+    }
+
+    #[test]
+    fn macros_keep_their_steps_apart() {
+        let conn = conn();
+        let doom = create(&conn, GAMES, fields("Doom", LaunchType::Bat, "doom.sh")).unwrap();
+        let session = create(&conn, GAMES, fields("Session", LaunchType::Macro, "")).unwrap();
+        let definition = Macro {
+            mode: macros::MacroMode::Sequential,
+            on_failure: macros::FailurePolicy::Stop,
+            steps: vec![macros::MacroStep {
+                entry_id: doom.id,
+                profile: None,
+                delay_ms: 0,
+            }],
+        };
+        save_macro(&conn, &session, Some(definition.clone())).unwrap();
+        assert_eq!(
+            save_macro(&conn, &session, None),
+            Err("A macro needs at least one step".into())
+        );
+
+        // A step cannot turn into a macro.
+        assert!(
+            update(&conn, doom.id, GAMES, fields("Doom", LaunchType::Macro, ""))
+                .unwrap_err()
+                .contains("step of a macro")
+        );
+        assert_eq!(macros::get(&conn, session.id).unwrap(), Some(definition));
+
+        // A macro that becomes a plain entry drops its steps.
+        update(
+            &conn,
+            session.id,
+            APPS,
+            fields("Session", LaunchType::Url, "https://x"),
+        )
+        .unwrap();
+        assert_eq!(macros::get(&conn, session.id).unwrap(), None);
+    }
+
+    #[test]
+    fn rejects_invalid_entries() {
+        let conn = conn();
+        let invalid = |change: fn(&mut EntryFields)| {
+            let mut entry = fields("Game", LaunchType::Exe, "game.exe");
+            change(&mut entry);
+            create(&conn, GAMES, entry).unwrap_err()
+        };
+
+        assert_eq!(invalid(|e| e.name = "  ".into()), "Name cannot be empty");
+        assert_eq!(
+            invalid(|e| e.launch_data = String::new()),
+            "Executable path cannot be empty"
+        );
+        assert!(invalid(|e| {
+            e.launch_type = LaunchType::Steam;
+            e.launch_data = "portal".into();
+        })
+        .starts_with("Steam App ID must be a number"));
+        assert!(invalid(|e| {
+            e.launch_type = LaunchType::Url;
+            e.launch_data = "example.com".into();
+        })
+        .starts_with("Invalid URL"));
+        assert!(invalid(|e| e.launch_args = r#"-x "open"#.into())
+            .starts_with("Invalid launch arguments"));
+        assert_eq!(
+            invalid(|e| e.env = BTreeMap::from([("1BAD".into(), "x".into())])),
+            "Invalid environment variable name: \"1BAD\""
+        );
+        assert_eq!(
+            invalid(|e| e.runners = Some(vec![Runner {
+                command: "wine".into(),
+                env: BTreeMap::from([("A=B".into(), "x".into())]),
+            }])),
+            "Invalid environment variable name: \"A=B\""
+        );
+        assert!(invalid(|e| e.hooks.post_exit.push(Hook {
+            command: "  ".into(),
+            timeout_secs: 5,
+        }))
+        .contains("is invalid"));
+        assert!(invalid(|e| e.runners = Some(vec![Runner {
+            command: String::new(),
+            env: BTreeMap::new(),
+        }]))
+        .contains("empty command"));
+        assert!(invalid(|e| e.image_hash = "../kscope.db".into()).starts_with("Not an image hash"));
+        assert_eq!(
+            create(&conn, 99, fields("Game", LaunchType::Exe, "game.exe")).unwrap_err(),
+            "Category 99 not found"
+        );
+        assert!(list(&conn, None).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,501 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::db::{json_column, unix_now, Database};
+use crate::launcher::{expand, runners, steam, LaunchType};
+
+/// Result of the last health scan per entry. `auto_deprecated` remembers
+/// that the scan (not the user) set `entries.deprecated`, so the flag can be
+/// cleared again once the entry is healthy.
+pub const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS entry_health (
+        entry_id INTEGER PRIMARY KEY REFERENCES entries(id) ON DELETE CASCADE,
+        health TEXT NOT NULL,
+        checked_at INTEGER NOT NULL,
+        auto_deprecated INTEGER NOT NULL DEFAULT 0
+    );
+";
+
+/// What a scan found for one entry.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Health {
+    Ok,
+    /// The executable or script does not exist.
+    Missing {
+        path: String,
+    },
+    /// The path exists but is a directory or lacks execute permission.
+    NotExecutable {
+        path: String,
+    },
+    /// Steam is installed but no library has an `appmanifest` for the app.
+    NotInstalled {
+        app_id: String,
+    },
+    InvalidUrl {
+        reason: String,
+    },
+    /// The entry could not be checked (e.g. Steam itself was not found).
+    Unknown {
+        reason: String,
+    },
+}
+
+impl Health {
+    pub fn is_broken(&self) -> bool {
+        !matches!(self, Self::Ok | Self::Unknown { .. })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EntryHealth {
+    pub entry_id: i64,
+    #[serde(flatten)]
+    pub health: Health,
+    pub checked_at: i64,
+    /// `deprecated` is currently set because of this scan.
+    pub auto_deprecated: bool,
+}
+
+// --- CHECKS ---
+
+/// Check the target of an entry. `wrapped` means it starts through a
+/// runner (e.g. `wine`), so it only has to exist. `steam_libraries` is
+/// `None` when no Steam installation was found. Paths are expanded the way
+/// the launcher expands them.
+pub fn check(
+    launch_type: LaunchType,
+    launch_data: &str,
+    wrapped: bool,
+    steam_libraries: Option<&[PathBuf]>,
+) -> Health {
+    let target = launch_data.trim();
+    // A variable that is not set here leaves nothing to look at.
+    let local = || expand::expand(target).unwrap_or_else(|_| target.to_string());
+    match launch_type {
+        LaunchType::Exe => check_file(&local(), !wrapped),
+        LaunchType::Bat => check_file(&local(), cfg!(windows)),
+        LaunchType::Steam => check_steam(target, steam_libraries),
+        LaunchType::Url => match validate_url(target) {
+            Ok(()) => Health::Ok,
+            Err(reason) => Health::InvalidUrl { reason },
+        },
+        // Its steps are checked as entries of their own.
+        LaunchType::Macro => Health::Ok,
+    }
+}
+
+/// Scripts on Linux and macOS are run through `sh`, so they only need to
+/// exist; executables need execute permission.
+fn check_file(target: &str, needs_exec: bool) -> Health {
+    let path = match find_program(target) {
+        Some(path) => path,
+        None => {
+            return Health::Missing {
+                path: target.to_string(),
+            }
+        }
+    };
+    match fs::metadata(&path) {
+        Err(_) => Health::Missing {
+            path: target.to_string(),
+        },
+        Ok(meta) if meta.is_dir() || (needs_exec && !is_executable(&meta)) => {
+            Health::NotExecutable {
+                path: target.to_string(),
+            }
+        }
+        Ok(_) => Health::Ok,
+    }
+}
+
+/// A path as given, or a bare program name looked up on `PATH`.
+fn find_program(target: &str) -> Option<PathBuf> {
+    let path = Path::new(target);
+    if target.is_empty() {
+        return None;
+    }
+    if path.components().count() > 1 || path.is_absolute() {
+        return Some(path.to_path_buf());
+    }
+    env::split_paths(&env::var_os("PATH")?)
+        .map(|dir| dir.join(path))
+        .find(|candidate| candidate.is_file())
+        .or_else(|| Some(path.to_path_buf()))
+}
+
+#[cfg(unix)]
+fn is_executable(meta: &fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    meta.permissions().mode() & 0o111 != 0
+}
+
+#[cfg(not(unix))]
+fn is_executable(_meta: &fs::Metadata) -> bool {
+    true
+}
+
+fn check_steam(app_id: &str, libraries: Option<&[PathBuf]>) -> Health {
+    if app_id.is_empty() || !app_id.chars().all(|c| c.is_ascii_digit()) {
+        return Health::NotInstalled {
+            app_id: app_id.to_string(),
+        };
+    }
+    let Some(libraries) = libraries else {
+        return Health::Unknown {
+            reason: "Steam installation not found".into(),
+        };
+    };
+    let manifest = format!("appmanifest_{app_id}.acf");
+    if libraries
+        .iter()
+        .any(|library| library.join("steamapps").join(&manifest).is_file())
+    {
+        Health::Ok
+    } else {
+        Health::NotInstalled {
+            app_id: app_id.to_string(),
+        }
+    }
+}
+
+/// Check that a URL has an RFC 3986 scheme and something after it. Web URLs
+/// additionally need a host.
+pub fn validate_url(url: &str) -> Result<(), String> {
+    if url.is_empty() {
+        return Err("URL is empty".into());
+    }
+    if url.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return Err("URL contains whitespace".into());
+    }
+    let (scheme, rest) = url
+        .split_once(':')
+        .ok_or_else(|| "URL has no scheme (e.g. https:)".to_string())?;
+
+    let mut chars = scheme.chars();
+    let valid_scheme = chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    if !valid_scheme {
+        return Err(format!("\"{scheme}\" is not a valid URL scheme"));
+    }
+    if rest.is_empty() {
+        return Err("URL has nothing after the scheme".into());
+    }
+
+    if scheme.eq_ignore_ascii_case("http") || scheme.eq_ignore_ascii_case("https") {
+        let host = rest
+            .strip_prefix("//")
+            .map(|rest| rest.split(['/', '?', '#']).next().unwrap_or_default())
+            .unwrap_or_default();
+        if host.is_empty() {
+            return Err("Web URL has no host".into());
+        }
+    }
+    Ok(())
+}
+
+// --- STEAM LIBRARIES ---
+
+/// Every Steam library folder, or `None` when Steam is not installed.
+pub fn steam_libraries() -> Option<Vec<PathBuf>> {
+    let root = steam::install_roots()
+        .into_iter()
+        .find(|root| root.join("steamapps").is_dir())?;
+
+    let mut libraries = vec![root.clone()];
+    let folders = root.join("steamapps").join("libraryfolders.vdf");
+    if let Ok(text) = fs::read_to_string(folders) {
+        for path in library_paths(&text) {
+            let path = PathBuf::from(path);
+            if !libraries.contains(&path) {
+                libraries.push(path);
+            }
+        }
+    }
+    Some(libraries)
+}
+
+/// The `"path"` values of a `libraryfolders.vdf` file. Only quoted tokens
+/// matter for this, so the nesting of the KeyValues format is ignored.
+fn library_paths(vdf: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut chars = vdf.chars();
+    while let Some(c) = chars.next() {
+        if c != '"' {
+            continue;
+        }
+        let mut token = String::new();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => break,
+                '\\' => token.extend(chars.next()),
+                c => token.push(c),
+            }
+        }
+        tokens.push(token);
+    }
+
+    tokens
+        .windows(2)
+        .filter(|pair| pair[0].eq_ignore_ascii_case("path"))
+        .map(|pair| pair[1].clone())
+        .collect()
+}
+
+// --- STORAGE ---
+
+/// Store scan results. With `auto_deprecate`, broken entries are marked
+/// deprecated and entries this scan deprecated earlier are restored once
+/// they are healthy again.
+pub fn record(
+    conn: &mut Connection,
+    results: &[(i64, Health)],
+    auto_deprecate: bool,
+    now: i64,
+) -> rusqlite::Result<()> {
+    let tx = conn.transaction()?;
+    for (entry_id, health) in results {
+        let json = serde_json::to_string(health)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+        tx.execute(
+            "INSERT INTO entry_health (entry_id, health, checked_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT (entry_id) DO UPDATE SET health = ?2, checked_at = ?3",
+            params![entry_id, json, now],
+        )?;
+
+        if !auto_deprecate {
+            continue;
+        }
+        if health.is_broken() {
+            let changed = tx.execute(
+                "UPDATE entries SET deprecated = 1 WHERE id = ?1 AND NOT deprecated",
+                params![entry_id],
+            )?;
+            if changed > 0 {
+                tx.execute(
+                    "UPDATE entry_health SET auto_deprecated = 1 WHERE entry_id = ?1",
+                    params![entry_id],
+                )?;
+            }
+        } else if *health == Health::Ok {
+            tx.execute(
+                "UPDATE entries SET deprecated = 0 WHERE id = ?1
+                 AND (SELECT auto_deprecated FROM entry_health WHERE entry_id = ?1)",
+                params![entry_id],
+            )?;
+            tx.execute(
+                "UPDATE entry_health SET auto_deprecated = 0 WHERE entry_id = ?1",
+                params![entry_id],
+            )?;
+        }
+    }
+    tx.commit()
+}
+
+pub fn load(conn: &Connection) -> rusqlite::Result<Vec<EntryHealth>> {
+    let mut stmt = conn.prepare(
+        "SELECT entry_id, health, checked_at, auto_deprecated FROM entry_health ORDER BY entry_id",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok(EntryHealth {
+            entry_id: row.get("entry_id")?,
+            health: json_column(row, "health")?,
+            checked_at: row.get("checked_at")?,
+            auto_deprecated: row.get("auto_deprecated")?,
+        })
+    })?;
+    rows.collect()
+}
+
+/// Check every entry and store the results. The database is only locked to
+/// read the entries and to write the results, not while the disk is probed.
+pub fn scan(db: &Database, auto_deprecate: bool) -> rusqlite::Result<Vec<EntryHealth>> {
+    let targets: Vec<(i64, LaunchType, String, bool)> = {
+        let conn = db.conn();
+        let global_runners = !runners::global(&conn)?.is_empty();
+        let mut stmt = conn.prepare(
+            "SELECT id, launch_type, launch_data, COALESCE(runners, 'null') AS runners
+             FROM entries",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            let own: Option<Vec<runners::Runner>> = json_column(row, "runners")?;
+            Ok((
+                row.get("id")?,
+                row.get("launch_type")?,
+                row.get("launch_data")?,
+                own.map_or(global_runners, |chain| !chain.is_empty()),
+            ))
+        })?;
+        rows.collect::<rusqlite::Result<_>>()?
+    };
+
+    let libraries = steam_libraries();
+    let results: Vec<(i64, Health)> = targets
+        .into_iter()
+        .map(|(id, launch_type, data, wrapped)| {
+            (id, check(launch_type, &data, wrapped, libraries.as_deref()))
+        })
+        .collect();
+
+    let mut conn = db.conn();
+    record(&mut conn, &results, auto_deprecate, unix_now())?;
+    load(&conn)
+}
+
+// --- COMMANDS ---
+
+/// Check every entry's target and store a status per entry. With
+/// `auto_deprecate`, broken entries are also marked deprecated. Runs off the
+/// main thread, so the UI can start it on launch without waiting.
+#[tauri::command(async)]
+pub fn scan_library_health(
+    db: State<'_, Database>,
+    auto_deprecate: Option<bool>,
+) -> Result<Vec<EntryHealth>, String> {
+    scan(&db, auto_deprecate.unwrap_or(false)).map_err(|e| e.to_string())
+}
+
+/// Results of the last scan, without scanning again.
+#[tauri::command]
+pub fn library_health(db: State<'_, Database>) -> Result<Vec<EntryHealth>, String> {
+    load(&db.conn()).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conn() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE entries (id INTEGER PRIMARY KEY, deprecated INTEGER DEFAULT 0);
+             INSERT INTO entries (id) VALUES (1), (2);",
+        )
+        .unwrap();
+        conn.execute_batch(SCHEMA).unwrap();
+        conn
+    }
+
+    fn deprecated(conn: &Connection, id: i64) -> bool {
+        conn.query_row(
+            "SELECT deprecated FROM entries WHERE id = ?1",
+            params![id],
+            |row| row.get(0),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn urls() {
+        assert_eq!(validate_url("https://example.com/path?q=1"), Ok(()));
+        assert_eq!(validate_url("steam://open/friends"), Ok(()));
+        assert_eq!(validate_url("mailto:someone@example.com"), Ok(()));
+        assert!(validate_url("").is_err());
+        assert!(validate_url("example.com").is_err());
+        assert!(validate_url("https://").is_err());
+        assert!(validate_url("https:///path").is_err());
+        assert!(validate_url("1http://example.com").is_err());
+        assert!(validate_url("https://exa mple.com").is_err());
+    }
+
+    #[test]
+    fn library_folders_vdf() {
+        let vdf = r#"
+            "libraryfolders"
+            {
+                "0"
+                {
+                    "path"		"C:\\Program Files (x86)\\Steam"
+                    "apps" { "220" "1234" }
+                }
+                "1"
+                {
+                    "path"		"/mnt/games/SteamLibrary"
+                }
+            }
+        "#;
+        assert_eq!(
+            library_paths(vdf),
+            [r"C:\Program Files (x86)\Steam", "/mnt/games/SteamLibrary"]
+        );
+    }
+
+    #[test]
+    fn steam_manifests() {
+        let library = env::temp_dir().join(format!("kscope-health-{}", std::process::id()));
+        fs::create_dir_all(library.join("steamapps")).unwrap();
+        fs::write(library.join("steamapps/appmanifest_220.acf"), "").unwrap();
+        let libraries = [library.clone()];
+
+        assert_eq!(check_steam("220", Some(&libraries)), Health::Ok);
+        assert!(check_steam("440", Some(&libraries)).is_broken());
+        assert!(!check_steam("440", None).is_broken());
+
+        fs::remove_dir_all(library).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn executables_need_permission() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = env::temp_dir().join(format!("kscope-exec-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let game = dir.join("game");
+        fs::write(&game, "").unwrap();
+        let game = game.to_str().unwrap();
+
+        fs::set_permissions(game, fs::Permissions::from_mode(0o644)).unwrap();
+        assert!(matches!(
+            check(LaunchType::Exe, game, false, None),
+            Health::NotExecutable { .. }
+        ));
+        assert_eq!(check(LaunchType::Bat, game, false, None), Health::Ok);
+        assert_eq!(check(LaunchType::Exe, game, true, None), Health::Ok);
+
+        fs::set_permissions(game, fs::Permissions::from_mode(0o755)).unwrap();
+        assert_eq!(check(LaunchType::Exe, game, false, None), Health::Ok);
+
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(matches!(
+            check(LaunchType::Exe, game, false, None),
+            Health::Missing { .. }
+        ));
+    }
+
+    #[test]
+    fn auto_deprecate_marks_and_restores() {
+        let mut conn = conn();
+        let missing = Health::Missing {
+            path: "/gone".into(),
+        };
+
+        record(
+            &mut conn,
+            &[(1, missing.clone()), (2, Health::Ok)],
+            false,
+            1,
+        )
+        .unwrap();
+        assert!(!deprecated(&conn, 1));
+
+        record(&mut conn, &[(1, missing)], true, 2).unwrap();
+        assert!(deprecated(&conn, 1));
+        assert!(load(&conn).unwrap()[0].auto_deprecated);
+
+        record(&mut conn, &[(1, Health::Ok)], true, 3).unwrap();
+        assert!(!deprecated(&conn, 1));
+
+        // Entries the user deprecated stay deprecated.
+        conn.execute("UPDATE entries SET deprecated = 1 WHERE id = 2", [])
+            .unwrap();
+        record(&mut conn, &[(2, Health::Ok)], true, 4).unwrap();
+        assert!(deprecated(&conn, 2));
+    }
+}
@@ -0,0 +1,452 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Cursor;
+use std::path::Path;
+
+use image::imageops::{self, FilterType};
+use image::{DynamicImage, ImageFormat, RgbaImage};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::db::Database;
+use crate::images::{self, ImageStore};
+use crate::settings;
+
+/// Settings key of the thumbnail sizes.
+const SETTINGS_KEY: &str = "thumbnails";
+
+/// Largest edge a thumbnail may be configured with.
+const MAX_EDGE: u32 = 2048;
+
+/// Where an image is shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThumbnailKind {
+    /// Game cover cards.
+    Grid,
+    /// App cards.
+    List,
+}
+
+impl ThumbnailKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Grid => "grid",
+            Self::List => "list",
+        }
+    }
+}
+
+/// How an image with another aspect ratio is made to fit the card.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Fit {
+    /// Fill the card and cut off what sticks out, keeping the centre.
+    Crop,
+    /// Show the whole image, centred on a transparent background.
+    Pad,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThumbnailSpec {
+    pub width: u32,
+    pub height: u32,
+    pub fit: Fit,
+}
+
+impl ThumbnailSpec {
+    /// Part of the thumbnail's file name, so changing a setting gives new
+    /// files instead of serving ones rendered under the old one.
+    fn key(&self) -> String {
+        let fit = match self.fit {
+            Fit::Crop => "crop",
+            Fit::Pad => "pad",
+        };
+        format!("{}x{}-{fit}", self.width, self.height)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThumbnailSettings {
+    pub grid: ThumbnailSpec,
+    pub list: ThumbnailSpec,
+}
+
+/// Twice the size of a 3:4 game card and a 2:1 app card, so they stay
+/// sharp on high-DPI screens. Covers are cropped; icons are padded, since
+/// cutting into an icon loses its shape.
+impl Default for ThumbnailSettings {
+    fn default() -> Self {
+        Self {
+            grid: ThumbnailSpec {
+                width: 384,
+                height: 512,
+                fit: Fit::Crop,
+            },
+            list: ThumbnailSpec {
+                width: 240,
+                height: 120,
+                fit: Fit::Pad,
+            },
+        }
+    }
+}
+
+impl ThumbnailSettings {
+    pub fn spec(&self, kind: ThumbnailKind) -> ThumbnailSpec {
+        match kind {
+            ThumbnailKind::Grid => self.grid,
+            ThumbnailKind::List => self.list,
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        for kind in [ThumbnailKind::Grid, ThumbnailKind::List] {
+            let spec = self.spec(kind);
+            if !(1..=MAX_EDGE).contains(&spec.width) || !(1..=MAX_EDGE).contains(&spec.height) {
+                return Err(format!(
+                    "{} thumbnails must be between 1 and {MAX_EDGE} pixels on each side",
+                    kind.as_str()
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn load_settings(conn: &Connection) -> rusqlite::Result<ThumbnailSettings> {
+    Ok(settings::get(conn, SETTINGS_KEY)?.unwrap_or_default())
+}
+
+/// Decode a PNG, JPEG, WebP or GIF image. Animated GIFs give their first
+/// frame.
+pub fn decode(bytes: &[u8]) -> Result<DynamicImage, String> {
+    let format = image::guess_format(bytes)
+        .ok()
+        .filter(|f| {
+            matches!(
+                f,
+                ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::WebP | ImageFormat::Gif
+            )
+        })
+        .ok_or("Not a PNG, JPEG, WebP or GIF image")?;
+    image::load_from_memory_with_format(bytes, format)
+        .map_err(|e| format!("Could not decode image: {e}"))
+}
+
+/// Scale an image to exactly the size in `spec`. Small icons are scaled up
+/// as well, so every card shows its art at the same size.
+pub fn render(image: &DynamicImage, spec: ThumbnailSpec) -> RgbaImage {
+    let (width, height) = (spec.width, spec.height);
+    match spec.fit {
+        Fit::Crop => {
+            // The largest centred region with the card's aspect ratio.
+            let (w, h) = (image.width() as u64, image.height() as u64);
+            let (crop_w, crop_h) = if w * height as u64 > h * width as u64 {
+                ((h * width as u64 / height as u64).max(1), h)
+            } else {
+                (w, (w * height as u64 / width as u64).max(1))
+            };
+            let x = (w - crop_w) / 2;
+            let y = (h - crop_h) / 2;
+            image
+                .crop_imm(x as u32, y as u32, crop_w as u32, crop_h as u32)
+                .resize_exact(width, height, FilterType::CatmullRom)
+                .to_rgba8()
+        }
+        Fit::Pad => {
+            let fitted = image
+                .resize(width, height, FilterType::CatmullRom)
+                .to_rgba8();
+            let mut canvas = RgbaImage::new(width, height);
+            let x = (width - fitted.width()) / 2;
+            let y = (height - fitted.height()) / 2;
+            imageops::overlay(&mut canvas, &fitted, x as i64, y as i64);
+            canvas
+        }
+    }
+}
+
+/// File name of a thumbnail in the image store: the original's hash, the
+/// kind and the settings it was rendered with.
+pub fn thumbnail_name(hash: &str, kind: ThumbnailKind, spec: ThumbnailSpec) -> String {
+    format!("{hash}.{}.{}", kind.as_str(), spec.key())
+}
+
+fn write_thumbnail(
+    store: &ImageStore,
+    name: &str,
+    image: &DynamicImage,
+    spec: ThumbnailSpec,
+) -> Result<(), String> {
+    let mut png = Cursor::new(Vec::new());
+    render(image, spec)
+        .write_to(&mut png, ImageFormat::Png)
+        .map_err(|e| e.to_string())?;
+    store.write(name, png.get_ref()).map_err(|e| e.to_string())
+}
+
+/// The thumbnail of a stored image, rendered on first use. Returns its file
+/// name in the store.
+pub fn thumbnail(
+    store: &ImageStore,
+    hash: &str,
+    kind: ThumbnailKind,
+    settings: &ThumbnailSettings,
+) -> Result<String, String> {
+    let spec = settings.spec(kind);
+    let name = thumbnail_name(hash, kind, spec);
+    if store.dir().join(&name).is_file() {
+        return Ok(name);
+    }
+    let bytes = store.get(hash).map_err(|e| e.to_string())?;
+    write_thumbnail(store, &name, &decode(&bytes)?, spec)?;
+    Ok(name)
+}
+
+/// Read an image file, check that it decodes, and add it to the store with
+/// both thumbnails. Returns its hash.
+pub fn import(
+    store: &ImageStore,
+    path: &Path,
+    settings: &ThumbnailSettings,
+) -> Result<String, String> {
+    let bytes = fs::read(path).map_err(|e| format!("Could not read {}: {e}", path.display()))?;
+    let image = decode(&bytes).map_err(|e| format!("{}: {e}", path.display()))?;
+    let hash = store.put(&bytes).map_err(|e| e.to_string())?;
+    for kind in [ThumbnailKind::Grid, ThumbnailKind::List] {
+        let spec = settings.spec(kind);
+        write_thumbnail(store, &thumbnail_name(&hash, kind, spec), &image, spec)?;
+    }
+    Ok(hash)
+}
+
+/// Remove thumbnails rendered under other settings. Originals are kept.
+pub fn prune(store: &ImageStore, settings: &ThumbnailSettings) -> std::io::Result<usize> {
+    let Ok(files) = fs::read_dir(store.dir()) else {
+        return Ok(0);
+    };
+    let current = [ThumbnailKind::Grid, ThumbnailKind::List]
+        .map(|kind| format!("{}.{}", kind.as_str(), settings.spec(kind).key()));
+    let mut removed = 0;
+    for file in files {
+        let file = file?;
+        let name = file.file_name().to_string_lossy().into_owned();
+        let Some((hash, rendered)) = name.split_once('.') else {
+            continue;
+        };
+        let stale = !name.ends_with(".partial") && !current.iter().any(|c| c == rendered);
+        if images::is_hash(hash) && stale {
+            fs::remove_file(file.path())?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+// --- COMMANDS ---
+
+/// Copy a picked image into the store and return its hash.
+#[tauri::command(async)]
+pub fn store_image(
+    db: State<'_, Database>,
+    images: State<'_, ImageStore>,
+    path: String,
+) -> Result<String, String> {
+    let settings = load_settings(&db.conn()).map_err(|e| e.to_string())?;
+    import(&images, Path::new(&path), &settings)
+}
+
+/// Thumbnail file names for the given images, rendering any that are
+/// missing. Images that cannot be read are left out, and the UI shows the
+/// original instead.
+#[tauri::command(async)]
+pub fn thumbnails(
+    db: State<'_, Database>,
+    images: State<'_, ImageStore>,
+    kind: ThumbnailKind,
+    hashes: Vec<String>,
+) -> Result<BTreeMap<String, String>, String> {
+    let settings = load_settings(&db.conn()).map_err(|e| e.to_string())?;
+    Ok(hashes
+        .into_iter()
+        .filter_map(|hash| {
+            let name = thumbnail(&images, &hash, kind, &settings).ok()?;
+            Some((hash, name))
+        })
+        .collect())
+}
+
+#[tauri::command]
+pub fn thumbnail_settings(db: State<'_, Database>) -> Result<ThumbnailSettings, String> {
+    load_settings(&db.conn()).map_err(|e| e.to_string())
+}
+
+/// Save new sizes. Thumbnails under the old ones are removed and the new
+/// ones rendered as they are asked for.
+#[tauri::command]
+pub fn save_thumbnail_settings(
+    db: State<'_, Database>,
+    images: State<'_, ImageStore>,
+    settings: ThumbnailSettings,
+) -> Result<(), String> {
+    settings.validate()?;
+    settings::set(&db.conn(), SETTINGS_KEY, &settings).map_err(|e| e.to_string())?;
+    prune(&images, &settings).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Rgba;
+
+    use super::*;
+
+    /// A store in a fresh temporary folder, removed again on drop.
+    struct TempStore(ImageStore);
+
+    impl TempStore {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir()
+                .join(format!("kscope-thumbnails-{}-{name}", std::process::id()));
+            fs::remove_dir_all(&dir).ok();
+            Self(ImageStore::new(dir))
+        }
+    }
+
+    impl Drop for TempStore {
+        fn drop(&mut self) {
+            fs::remove_dir_all(self.0.dir()).ok();
+        }
+    }
+
+    const RED: Rgba<u8> = Rgba([255, 0, 0, 255]);
+    const BLUE: Rgba<u8> = Rgba([0, 0, 255, 255]);
+
+    /// A wide image: red on the left half, blue on the right.
+    fn halves(width: u32, height: u32) -> DynamicImage {
+        DynamicImage::ImageRgba8(RgbaImage::from_fn(width, height, |x, _| {
+            if x < width / 2 {
+                RED
+            } else {
+                BLUE
+            }
+        }))
+    }
+
+    fn encode(image: &DynamicImage, format: ImageFormat) -> Vec<u8> {
+        let mut bytes = Cursor::new(Vec::new());
+        image.write_to(&mut bytes, format).unwrap();
+        bytes.into_inner()
+    }
+
+    fn spec(width: u32, height: u32, fit: Fit) -> ThumbnailSpec {
+        ThumbnailSpec { width, height, fit }
+    }
+
+    #[test]
+    fn decodes_supported_formats() {
+        let image = halves(8, 4);
+        for format in [
+            ImageFormat::Png,
+            ImageFormat::Jpeg,
+            ImageFormat::WebP,
+            ImageFormat::Gif,
+        ] {
+            let image = match format {
+                ImageFormat::Jpeg => DynamicImage::ImageRgb8(image.to_rgb8()),
+                _ => image.clone(),
+            };
+            let decoded = decode(&encode(&image, format)).unwrap();
+            assert_eq!((decoded.width(), decoded.height()), (8, 4), "{format:?}");
+        }
+        assert!(decode(b"BM not supported").is_err());
+        assert!(decode(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]).is_err());
+    }
+
+    #[test]
+    fn crop_keeps_the_centre() {
+        // 4:1 into 1:1 keeps the middle half, which is half red, half blue.
+        let thumb = render(&halves(400, 100), spec(10, 10, Fit::Crop));
+        assert_eq!(thumb.dimensions(), (10, 10));
+        assert_eq!(*thumb.get_pixel(0, 5), RED);
+        assert_eq!(*thumb.get_pixel(9, 5), BLUE);
+
+        // A tall image loses its top and bottom instead.
+        let tall = render(&halves(100, 400), spec(30, 40, Fit::Crop));
+        assert_eq!(tall.dimensions(), (30, 40));
+    }
+
+    #[test]
+    fn pad_centres_on_transparent() {
+        let thumb = render(&halves(40, 10), spec(40, 40, Fit::Pad));
+        assert_eq!(thumb.dimensions(), (40, 40));
+        assert_eq!(thumb.get_pixel(20, 0)[3], 0);
+        assert_eq!(thumb.get_pixel(20, 39)[3], 0);
+        assert_eq!(*thumb.get_pixel(0, 20), RED);
+        assert_eq!(*thumb.get_pixel(39, 20), BLUE);
+
+        // Tiny icons are scaled up to fill the card's height.
+        let icon = render(&halves(4, 4), spec(240, 120, Fit::Pad));
+        assert_eq!(icon.dimensions(), (240, 120));
+        assert_eq!(icon.get_pixel(59, 60)[3], 0);
+        assert_eq!(icon.get_pixel(100, 60)[3], 255);
+    }
+
+    #[test]
+    fn thumbnails_are_cached_per_setting() {
+        let store = TempStore::new("cache");
+        let store = &store.0;
+        let source = store.dir().with_extension("png");
+        fs::write(&source, encode(&halves(64, 32), ImageFormat::Png)).unwrap();
+
+        let settings = ThumbnailSettings::default();
+        let hash = import(store, &source, &settings).unwrap();
+        fs::remove_file(&source).unwrap();
+        let grid = thumbnail_name(&hash, ThumbnailKind::Grid, settings.grid);
+        assert!(store.dir().join(&grid).is_file());
+        assert_eq!(
+            thumbnail(store, &hash, ThumbnailKind::Grid, &settings).unwrap(),
+            grid
+        );
+
+        // New sizes render new files on demand; pruning drops the old ones.
+        let changed = ThumbnailSettings {
+            grid: spec(96, 128, Fit::Pad),
+            ..settings
+        };
+        let new_grid = thumbnail(store, &hash, ThumbnailKind::Grid, &changed).unwrap();
+        assert_ne!(new_grid, grid);
+        let thumb = decode(&fs::read(store.dir().join(&new_grid)).unwrap()).unwrap();
+        assert_eq!((thumb.width(), thumb.height()), (96, 128));
+
+        assert_eq!(prune(store, &changed).unwrap(), 1);
+        assert!(!store.dir().join(&grid).is_file());
+        assert!(store.contains(&hash));
+        assert!(store
+            .dir()
+            .join(thumbnail_name(&hash, ThumbnailKind::List, changed.list))
+            .is_file());
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        let store = TempStore::new("bad");
+        let settings = ThumbnailSettings::default();
+        let source = store.0.dir().with_extension("txt");
+        fs::write(&source, "not an image").unwrap();
+        assert!(import(&store.0, &source, &settings)
+            .unwrap_err()
+            .contains("Not a PNG"));
+        fs::remove_file(&source).unwrap();
+        assert!(thumbnail(&store.0, "../kscope.db", ThumbnailKind::Grid, &settings).is_err());
+
+        let too_big = ThumbnailSettings {
+            list: spec(4096, 10, Fit::Pad),
+            ..settings
+        };
+        assert!(too_big.validate().is_err());
+        assert!(settings.validate().is_ok());
+    }
+}
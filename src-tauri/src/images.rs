@@ -0,0 +1,170 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf, MAIN_SEPARATOR};
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use sha2::{Digest, Sha256};
+use tauri::State;
+
+/// Folder in the app data directory that holds cover art and icons.
+pub const IMAGE_DIR: &str = "images";
+
+/// Cover art and icons, stored once per distinct content under the
+/// SHA-256 of their bytes. Entries keep only the hash, and the webview
+/// loads the file itself through the asset protocol. Managed as Tauri
+/// state.
+pub struct ImageStore {
+    dir: PathBuf,
+}
+
+impl ImageStore {
+    /// A store in `dir`, created on the first [`put`](Self::put).
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Where the image with this hash lives. Files have no extension; the
+    /// asset protocol tells the type from the content.
+    pub fn path(&self, hash: &str) -> PathBuf {
+        self.dir.join(hash)
+    }
+
+    pub fn contains(&self, hash: &str) -> bool {
+        is_hash(hash) && self.path(hash).is_file()
+    }
+
+    /// Add an image and return its hash. Content that is already stored is
+    /// not written again. The file is written next to its final name and
+    /// renamed into place, so a reader never sees half an image.
+    pub fn put(&self, bytes: &[u8]) -> io::Result<String> {
+        let hash = hash(bytes);
+        if !self.path(&hash).is_file() {
+            self.write(&hash, bytes)?;
+        }
+        Ok(hash)
+    }
+
+    /// Write a file into the store folder, replacing any file of that name
+    /// in one step.
+    pub fn write(&self, name: &str, bytes: &[u8]) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let partial = self.dir.join(format!("{name}.partial"));
+        fs::write(&partial, bytes)?;
+        fs::rename(&partial, self.dir.join(name))
+    }
+
+    pub fn get(&self, hash: &str) -> io::Result<Vec<u8>> {
+        if !is_hash(hash) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Not an image hash: {hash}"),
+            ));
+        }
+        fs::read(self.path(hash))
+    }
+}
+
+/// Lowercase hex SHA-256 of `bytes`.
+pub fn hash(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// Whether `s` is a hash as returned by [`hash`]. Anything else is refused
+/// before it can be used as a file name.
+pub fn is_hash(s: &str) -> bool {
+    s.len() == 64 && s.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+/// The bytes of a base64 `data:` URL, as the webview used to store in
+/// `entries.image_data`.
+pub fn decode_data_url(url: &str) -> Option<Vec<u8>> {
+    let (header, data) = url.strip_prefix("data:")?.split_once(',')?;
+    if !header.ends_with(";base64") {
+        return None;
+    }
+    STANDARD.decode(data.trim()).ok()
+}
+
+// --- COMMANDS ---
+
+/// The store folder with a trailing separator, so the webview can append a
+/// hash and hand the path to `convertFileSrc`.
+#[tauri::command]
+pub fn image_dir(images: State<'_, ImageStore>) -> String {
+    format!("{}{MAIN_SEPARATOR}", images.dir().display())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A store in a fresh temporary folder, removed again on drop.
+    struct TempStore(ImageStore);
+
+    impl TempStore {
+        fn new(name: &str) -> Self {
+            let dir =
+                std::env::temp_dir().join(format!("kscope-images-{}-{name}", std::process::id()));
+            fs::remove_dir_all(&dir).ok();
+            Self(ImageStore::new(dir))
+        }
+    }
+
+    impl Drop for TempStore {
+        fn drop(&mut self) {
+            fs::remove_dir_all(self.0.dir()).ok();
+        }
+    }
+
+    const PNG: &[u8] = &[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, 1, 2, 3];
+
+    #[test]
+    fn stores_by_content() {
+        let store = TempStore::new("content");
+        let store = &store.0;
+        let first = store.put(PNG).unwrap();
+        assert!(is_hash(&first));
+        assert!(store.contains(&first));
+        assert_eq!(store.get(&first).unwrap(), PNG);
+
+        // Same bytes, same file; different bytes, another one.
+        assert_eq!(store.put(PNG).unwrap(), first);
+        let other = store.put(b"GIF89a").unwrap();
+        assert_ne!(other, first);
+        assert_eq!(fs::read_dir(store.dir()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn known_hash() {
+        assert_eq!(
+            hash(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn refuses_paths() {
+        let store = TempStore::new("refuse");
+        assert!(!is_hash("../kscope.db"));
+        assert!(!store.0.contains("../kscope.db"));
+        assert!(store.0.get("../kscope.db").is_err());
+    }
+
+    #[test]
+    fn data_urls() {
+        assert_eq!(
+            decode_data_url("data:image/png;base64,AAAA"),
+            Some(vec![0, 0, 0])
+        );
+        assert_eq!(decode_data_url("data:text/plain,hello"), None);
+        assert_eq!(decode_data_url("/images/cover.png"), None);
+    }
+}
@@ -0,0 +1,347 @@
+use std::fmt;
+
+use serde::Serialize;
+
+/// Why an argument string could not be split.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ArgsError {
+    /// A quote was opened at `position` (character index) and never closed.
+    UnbalancedQuote { quote: char, position: usize },
+    /// The string ends in a lone backslash (POSIX only).
+    TrailingEscape { position: usize },
+}
+
+impl fmt::Display for ArgsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnbalancedQuote { quote, position } => {
+                write!(f, "Unclosed {quote} quote opened at position {position}")
+            }
+            Self::TrailingEscape { position } => {
+                write!(
+                    f,
+                    "Trailing backslash at position {position} escapes nothing"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ArgsError {}
+
+/// Split an argument string with the rules of the platform we launch on.
+pub fn split(args: &str) -> Result<Vec<String>, ArgsError> {
+    if cfg!(windows) {
+        split_windows(args)
+    } else {
+        split_posix(args)
+    }
+}
+
+/// Split the way `CommandLineToArgvW` splits everything after the program
+/// name:
+///
+/// - spaces and tabs separate arguments outside quotes;
+/// - `2n` backslashes before a `"` become `n` backslashes and the quote
+///   opens or closes a quoted run; `2n + 1` backslashes become `n` and a
+///   literal `"`;
+/// - backslashes not followed by `"` are literal;
+/// - `""` inside a quoted run is a literal `"`;
+/// - `""` on its own is an empty argument.
+///
+/// Windows silently accepts a quote that is never closed; we reject it.
+pub fn split_windows(args: &str) -> Result<Vec<String>, ArgsError> {
+    let chars: Vec<char> = args.chars().collect();
+    let mut out = Vec::new();
+    let mut current = String::new();
+    let mut started = false;
+    let mut quote_start = None;
+    let mut i = 0;
+
+    while i < chars.len() {
+        match chars[i] {
+            ' ' | '\t' if quote_start.is_none() => {
+                if started {
+                    out.push(std::mem::take(&mut current));
+                    started = false;
+                }
+                i += 1;
+            }
+            '\\' => {
+                let run = chars[i..].iter().take_while(|&&c| c == '\\').count();
+                i += run;
+                started = true;
+                if chars.get(i) == Some(&'"') {
+                    current.extend(std::iter::repeat_n('\\', run / 2));
+                    if run % 2 == 1 {
+                        current.push('"');
+                        i += 1;
+                    }
+                    // An even run leaves the quote for the next iteration.
+                } else {
+                    current.extend(std::iter::repeat_n('\\', run));
+                }
+            }
+            '"' => {
+                started = true;
+                if quote_start.is_some() {
+                    if chars.get(i + 1) == Some(&'"') {
+                        current.push('"');
+                        i += 2;
+                        continue;
+                    }
+                    quote_start = None;
+                } else {
+                    quote_start = Some(i);
+                }
+                i += 1;
+            }
+            c => {
+                started = true;
+                current.push(c);
+                i += 1;
+            }
+        }
+    }
+
+    if let Some(position) = quote_start {
+        return Err(ArgsError::UnbalancedQuote {
+            quote: '"',
+            position,
+        });
+    }
+    if started {
+        out.push(current);
+    }
+    Ok(out)
+}
+
+/// Split with POSIX shell word rules, without any expansion:
+///
+/// - unquoted whitespace separates arguments;
+/// - an unquoted `\` makes the next character literal (`\` + newline is a
+///   line continuation);
+/// - `'...'` is fully literal;
+/// - inside `"..."` a `\` only escapes `$`, `` ` ``, `"`, `\` and newline;
+/// - `''` and `""` are empty arguments.
+pub fn split_posix(args: &str) -> Result<Vec<String>, ArgsError> {
+    let chars: Vec<char> = args.chars().collect();
+    let mut out = Vec::new();
+    let mut current = String::new();
+    let mut started = false;
+    let mut i = 0;
+
+    while i < chars.len() {
+        match chars[i] {
+            c if c.is_whitespace() => {
+                if started {
+                    out.push(std::mem::take(&mut current));
+                    started = false;
+                }
+                i += 1;
+            }
+            '\\' => match chars.get(i + 1) {
+                None => return Err(ArgsError::TrailingEscape { position: i }),
+                Some('\n') => i += 2,
+                Some(&c) => {
+                    current.push(c);
+                    started = true;
+                    i += 2;
+                }
+            },
+            '\'' => {
+                let open = i;
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        None => {
+                            return Err(ArgsError::UnbalancedQuote {
+                                quote: '\'',
+                                position: open,
+                            })
+                        }
+                        Some('\'') => break,
+                        Some(&c) => current.push(c),
+                    }
+                    i += 1;
+                }
+                started = true;
+                i += 1;
+            }
+            '"' => {
+                let open = i;
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        None => {
+                            return Err(ArgsError::UnbalancedQuote {
+                                quote: '"',
+                                position: open,
+                            })
+                        }
+                        Some('"') => break,
+                        Some('\\') => match chars.get(i + 1) {
+                            Some('\n') => i += 1,
+                            Some(&c @ ('$' | '`' | '"' | '\\')) => {
+                                current.push(c);
+                                i += 1;
+                            }
+                            _ => current.push('\\'),
+                        },
+                        Some(&c) => current.push(c),
+                    }
+                    i += 1;
+                }
+                started = true;
+                i += 1;
+            }
+            c => {
+                current.push(c);
+                started = true;
+                i += 1;
+            }
+        }
+    }
+
+    if started {
+        out.push(current);
+    }
+    Ok(out)
+}
+
+/// The inverse of [`split_posix`]: arguments that need it are wrapped in
+/// single quotes, with `'` written as `'\''`.
+pub fn join_posix(args: &[String]) -> String {
+    args.iter()
+        .map(|arg| {
+            let plain = !arg.is_empty()
+                && arg
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c));
+            if plain {
+                arg.clone()
+            } else {
+                format!("'{}'", arg.replace('\'', r"'\''"))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Fixture = (&'static str, Result<&'static [&'static str], ArgsError>);
+
+    fn check(split: fn(&str) -> Result<Vec<String>, ArgsError>, fixtures: &[Fixture]) {
+        for (input, expected) in fixtures {
+            let expected = expected
+                .clone()
+                .map(|args| args.iter().map(|s| s.to_string()).collect::<Vec<_>>());
+            assert_eq!(split(input), expected, "input: {input:?}");
+        }
+    }
+
+    #[test]
+    fn windows_fixtures() {
+        check(
+            split_windows,
+            &[
+                ("", Ok(&[])),
+                ("   \t ", Ok(&[])),
+                ("--fullscreen --dx12", Ok(&["--fullscreen", "--dx12"])),
+                ("  a   b  ", Ok(&["a", "b"])),
+                (r#""mod path" -x"#, Ok(&["mod path", "-x"])),
+                (r#"a""b"#, Ok(&["ab"])),
+                (r#""" b"#, Ok(&["", "b"])),
+                (r#"a "" b"#, Ok(&["a", "", "b"])),
+                (r#""a""b""#, Ok(&[r#"a"b"#])),
+                (r#"\"quoted\""#, Ok(&[r#""quoted""#])),
+                (r#"a\\\"b"#, Ok(&[r#"a\"b"#])),
+                (r#"a\\"b c" d"#, Ok(&[r#"a\b c"#, "d"])),
+                (
+                    r#"C:\Games\My Game\ -x"#,
+                    Ok(&[r"C:\Games\My", r"Game\", "-x"]),
+                ),
+                (
+                    r#""C:\Program Files\\" next"#,
+                    Ok(&[r"C:\Program Files\", "next"]),
+                ),
+                (r#"-name="Big Map""#, Ok(&["-name=Big Map"])),
+                (
+                    r#"--open "unterminated"#,
+                    Err(ArgsError::UnbalancedQuote {
+                        quote: '"',
+                        position: 7,
+                    }),
+                ),
+                ("it's", Ok(&["it's"])),
+            ],
+        );
+    }
+
+    #[test]
+    fn posix_fixtures() {
+        check(
+            split_posix,
+            &[
+                ("", Ok(&[])),
+                (" \t\n ", Ok(&[])),
+                ("--fullscreen --dx12", Ok(&["--fullscreen", "--dx12"])),
+                (r#""mod path" -x"#, Ok(&["mod path", "-x"])),
+                ("'single $HOME' x", Ok(&["single $HOME", "x"])),
+                ("''", Ok(&[""])),
+                (r#"a "" b"#, Ok(&["a", "", "b"])),
+                (r#"a\ b"#, Ok(&["a b"])),
+                (r#""say \"hi\"""#, Ok(&[r#"say "hi""#])),
+                (r#""keep \n""#, Ok(&[r"keep \n"])),
+                (r#""\$HOME \\""#, Ok(&[r"$HOME \"])),
+                (r#"'it'\''s'"#, Ok(&["it's"])),
+                ("a\\\nb", Ok(&["ab"])),
+                (r#"pre"mid"'post'"#, Ok(&["premidpost"])),
+                (
+                    "'open",
+                    Err(ArgsError::UnbalancedQuote {
+                        quote: '\'',
+                        position: 0,
+                    }),
+                ),
+                (
+                    r#"x "open"#,
+                    Err(ArgsError::UnbalancedQuote {
+                        quote: '"',
+                        position: 2,
+                    }),
+                ),
+                ("end\\", Err(ArgsError::TrailingEscape { position: 3 })),
+            ],
+        );
+    }
+
+    #[test]
+    fn join_posix_round_trips() {
+        let cases: &[&[&str]] = &[
+            &[],
+            &["--mode", "fortress"],
+            &[
+                "My Game",
+                "",
+                "it's",
+                r#"say "hi""#,
+                "$HOME",
+                r"C:\x",
+                "a\nb",
+            ],
+        ];
+        for args in cases {
+            let args: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+            assert_eq!(split_posix(&join_posix(&args)).unwrap(), args);
+        }
+        assert_eq!(
+            join_posix(&["--name=x".into(), "two words".into()]),
+            "--name=x 'two words'"
+        );
+    }
+}
@@ -0,0 +1,324 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::State;
+
+use super::tracker::ProcessRegistry;
+
+/// How long `stop_entry` waits after a graceful terminate before it kills.
+const GRACE_PERIOD: Duration = Duration::from_secs(5);
+/// How long to wait for a force-killed tree to be reaped.
+const KILL_WAIT: Duration = Duration::from_secs(2);
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StopOutcome {
+    /// Nothing tracked was running for the entry.
+    NotRunning,
+    /// Everything exited after the graceful terminate.
+    Exited,
+    /// At least one process had to be force-killed.
+    Killed,
+    /// Processes were still alive after being force-killed.
+    StillRunning,
+}
+
+/// Returned by `stop_entry` and `kill_entry`.
+#[derive(Debug, Clone, Serialize)]
+pub struct StopReport {
+    pub entry_id: i64,
+    /// Root pids of the process trees that were signalled.
+    pub pids: Vec<u32>,
+    pub outcome: StopOutcome,
+}
+
+/// Root pids whose process tree is still alive. A root that has exited can
+/// leave children behind in its process group, so the registry alone is not
+/// enough.
+fn survivors(processes: &ProcessRegistry, entry_id: i64, pids: &[u32]) -> Vec<u32> {
+    let tracked = processes.pids(entry_id);
+    pids.iter()
+        .copied()
+        .filter(|pid| tracked.contains(pid) || platform::group_alive(*pid))
+        .collect()
+}
+
+fn wait_for_exit(
+    processes: &ProcessRegistry,
+    entry_id: i64,
+    pids: &[u32],
+    timeout: Duration,
+) -> bool {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if survivors(processes, entry_id, pids).is_empty() {
+            return true;
+        }
+        if Instant::now() >= deadline {
+            return false;
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+fn force_kill(processes: &ProcessRegistry, entry_id: i64, pids: Vec<u32>) -> StopReport {
+    for &pid in &pids {
+        platform::kill_tree(pid);
+    }
+    let outcome = if wait_for_exit(processes, entry_id, &pids, KILL_WAIT) {
+        StopOutcome::Killed
+    } else {
+        StopOutcome::StillRunning
+    };
+    StopReport {
+        entry_id,
+        pids,
+        outcome,
+    }
+}
+
+fn not_running(entry_id: i64) -> StopReport {
+    StopReport {
+        entry_id,
+        pids: Vec::new(),
+        outcome: StopOutcome::NotRunning,
+    }
+}
+
+/// See [`stop_entry`]. `grace` is how long terminated trees get to exit.
+fn stop(processes: &ProcessRegistry, entry_id: i64, grace: Duration) -> StopReport {
+    let pids = processes.pids(entry_id);
+    if pids.is_empty() {
+        return not_running(entry_id);
+    }
+
+    for &pid in &pids {
+        platform::terminate_tree(pid);
+    }
+    if wait_for_exit(processes, entry_id, &pids, grace) {
+        return StopReport {
+            entry_id,
+            pids,
+            outcome: StopOutcome::Exited,
+        };
+    }
+
+    let survivors = survivors(processes, entry_id, &pids);
+    force_kill(processes, entry_id, survivors)
+}
+
+/// See [`kill_entry`].
+fn kill(processes: &ProcessRegistry, entry_id: i64) -> StopReport {
+    let pids = processes.pids(entry_id);
+    if pids.is_empty() {
+        return not_running(entry_id);
+    }
+    force_kill(processes, entry_id, pids)
+}
+
+// --- COMMANDS ---
+
+/// Ask every tracked process tree of an entry to close, then force-kill
+/// whatever is still alive after the grace period.
+#[tauri::command(async)]
+pub fn stop_entry(processes: State<'_, ProcessRegistry>, id: i64) -> StopReport {
+    stop(&processes, id, GRACE_PERIOD)
+}
+
+/// Force-kill every tracked process tree of an entry right away.
+#[tauri::command(async)]
+pub fn kill_entry(processes: State<'_, ProcessRegistry>, id: i64) -> StopReport {
+    kill(&processes, id)
+}
+
+#[cfg(test)]
+pub(super) use platform::group_alive;
+pub(super) use platform::kill_tree;
+
+#[cfg(unix)]
+mod platform {
+    /// Tracked children lead their own process group (see
+    /// `LaunchCommand::to_command`), so signalling `-pid` reaches everything
+    /// they started.
+    pub fn terminate_tree(pid: u32) {
+        signal_group(pid, libc::SIGTERM);
+    }
+
+    pub fn kill_tree(pid: u32) {
+        signal_group(pid, libc::SIGKILL);
+    }
+
+    /// Whether any process of the group is still running. The group outlives
+    /// its leader, so this also works once the root has been reaped. Killed
+    /// children can linger as zombies until their new parent reaps them;
+    /// those do not count.
+    pub fn group_alive(pgid: u32) -> bool {
+        let Ok(pgid) = libc::pid_t::try_from(pgid) else {
+            return false;
+        };
+        // SAFETY: kill(2) has no memory-safety preconditions.
+        if unsafe { libc::kill(-pgid, 0) } != 0 {
+            return false;
+        }
+        #[cfg(target_os = "linux")]
+        return running_in_group(pgid);
+        #[cfg(not(target_os = "linux"))]
+        return true;
+    }
+
+    /// Scan `/proc` for a member of the group that is not a zombie.
+    #[cfg(target_os = "linux")]
+    fn running_in_group(pgid: libc::pid_t) -> bool {
+        let Ok(dir) = std::fs::read_dir("/proc") else {
+            return true;
+        };
+        dir.flatten().any(|proc| {
+            let Ok(stat) = std::fs::read_to_string(proc.path().join("stat")) else {
+                return false;
+            };
+            // "pid (comm) state ppid pgrp ..."; comm may contain spaces.
+            let Some((_, fields)) = stat.rsplit_once(')') else {
+                return false;
+            };
+            let mut fields = fields.split_whitespace();
+            let state = fields.next();
+            let group = fields.nth(1).and_then(|g| g.parse::<libc::pid_t>().ok());
+            group == Some(pgid) && state != Some("Z")
+        })
+    }
+
+    /// The group is signalled as a whole, never the bare pid: once the root
+    /// has been reaped its pid may belong to an unrelated process.
+    fn signal_group(pid: u32, signal: libc::c_int) {
+        let Ok(pid) = libc::pid_t::try_from(pid) else {
+            return;
+        };
+        // SAFETY: kill(2) has no memory-safety preconditions.
+        unsafe {
+            libc::kill(-pid, signal);
+        }
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::os::windows::process::CommandExt;
+    use std::process::{Command, Stdio};
+
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    /// Without `/F`, taskkill asks windows of the tree to close.
+    pub fn terminate_tree(pid: u32) {
+        taskkill(pid, false);
+    }
+
+    pub fn kill_tree(pid: u32) {
+        taskkill(pid, true);
+    }
+
+    /// Windows has no process groups to query; `taskkill /T` already
+    /// walks the tree while the root is alive, so the registry decides.
+    pub fn group_alive(_pid: u32) -> bool {
+        false
+    }
+
+    fn taskkill(pid: u32, force: bool) {
+        let mut command = Command::new("taskkill");
+        command
+            .args(["/PID", &pid.to_string(), "/T"])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .creation_flags(CREATE_NO_WINDOW);
+        if force {
+            command.arg("/F");
+        }
+        let _ = command.status();
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::io::{BufRead, BufReader};
+    use std::process::Stdio;
+
+    use super::*;
+    use crate::launcher::platform::LaunchCommand;
+
+    const ENTRY: i64 = 1;
+
+    /// Start `script` in its own process group and track it, once it has
+    /// printed a line to say its traps are in place.
+    fn start(processes: &ProcessRegistry, script: &str) -> u32 {
+        let mut child = LaunchCommand::new("sh")
+            .args(["-c", script])
+            .to_command()
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut ready = String::new();
+        BufReader::new(child.stdout.take().unwrap())
+            .read_line(&mut ready)
+            .unwrap();
+        processes.watch(ENTRY, child, |_| {}).pid
+    }
+
+    #[test]
+    fn graceful_terminate_reports_exited() {
+        let processes = ProcessRegistry::default();
+        let pid = start(&processes, "echo ready; sleep 30");
+
+        let report = stop(&processes, ENTRY, Duration::from_secs(5));
+        assert_eq!(report.pids, [pid]);
+        assert_eq!(report.outcome, StopOutcome::Exited);
+        assert!(!platform::group_alive(pid));
+    }
+
+    #[test]
+    fn ignored_terminate_escalates_to_kill() {
+        let processes = ProcessRegistry::default();
+        let pid = start(&processes, r#"trap "" TERM; echo ready; sleep 30"#);
+        let grace = Duration::from_millis(300);
+        let started = Instant::now();
+
+        let report = stop(&processes, ENTRY, grace);
+        assert!(started.elapsed() >= grace);
+        assert_eq!(report.pids, [pid]);
+        assert_eq!(report.outcome, StopOutcome::Killed);
+        assert!(!platform::group_alive(pid));
+        assert!(processes.pids(ENTRY).is_empty());
+    }
+
+    #[test]
+    fn surviving_children_are_killed_after_the_root_exits() {
+        let processes = ProcessRegistry::default();
+        // The root dies on TERM; the subshell and its sleep ignore it.
+        let pid = start(&processes, r#"(trap "" TERM; echo ready; sleep 30) & wait"#);
+
+        let report = stop(&processes, ENTRY, Duration::from_millis(300));
+        assert_eq!(report.outcome, StopOutcome::Killed);
+        assert!(!platform::group_alive(pid));
+    }
+
+    #[test]
+    fn nothing_to_stop() {
+        let processes = ProcessRegistry::default();
+        assert_eq!(
+            stop(&processes, ENTRY, GRACE_PERIOD).outcome,
+            StopOutcome::NotRunning
+        );
+        assert_eq!(kill(&processes, ENTRY).outcome, StopOutcome::NotRunning);
+    }
+
+    #[test]
+    fn kill_reports_killed() {
+        let processes = ProcessRegistry::default();
+        let pid = start(&processes, r#"trap "" TERM; echo ready; sleep 30"#);
+
+        let report = kill(&processes, ENTRY);
+        assert_eq!(report.pids, [pid]);
+        assert_eq!(report.outcome, StopOutcome::Killed);
+        assert!(!platform::group_alive(pid));
+    }
+}
@@ -0,0 +1,233 @@
+use std::fmt;
+use std::io;
+
+use serde::{Serialize, Serializer};
+
+use super::args::ArgsError;
+use super::hooks::HookError;
+
+/// Why a launch did not happen. Serialized with a `kind` tag so the UI can
+/// pick a message per variant.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum LaunchError {
+    /// No entry with this id exists.
+    EntryNotFound {
+        id: i64,
+    },
+    /// The requested launch profile does not exist for the entry.
+    ProfileNotFound {
+        name: String,
+    },
+    /// The executable or script does not exist.
+    NotFound {
+        path: String,
+    },
+    /// The target exists but may not be executed.
+    PermissionDenied {
+        path: String,
+    },
+    InvalidSteamId {
+        app_id: String,
+    },
+    InvalidArgs {
+        error: ArgsError,
+    },
+    /// A path or argument refers to an environment variable that is not
+    /// set, or uses `~` without a home folder.
+    UndefinedVariable {
+        name: String,
+    },
+    /// A runner in the entry's or the global chain cannot be split into a
+    /// command.
+    InvalidRunner {
+        command: String,
+        reason: String,
+    },
+    /// Spawning failed for another reason. `reason` is the name of the
+    /// `io::ErrorKind`, `message` the OS error text.
+    SpawnFailed {
+        #[serde(serialize_with = "error_kind_name")]
+        reason: io::ErrorKind,
+        message: String,
+    },
+    /// A pre-launch hook could not start or exited unsuccessfully.
+    HookFailed {
+        command: String,
+        code: Option<i32>,
+        message: String,
+    },
+    /// A pre-launch hook outlived its timeout and was killed.
+    Timeout {
+        command: String,
+        secs: u64,
+    },
+    /// A macro was started where a single program was expected, such as a
+    /// step of another macro.
+    NestedMacro {
+        name: String,
+    },
+    /// A macro entry was previewed; it runs its steps, not a command of
+    /// its own.
+    MacroHasNoCommand {
+        name: String,
+    },
+    /// A macro entry has no saved steps.
+    MacroNotFound {
+        id: i64,
+    },
+    /// Steam was started silently but its client did not show up in time.
+    SteamNotStarted {
+        secs: u64,
+    },
+    /// Reading or writing the library failed.
+    Database {
+        message: String,
+    },
+}
+
+fn error_kind_name<S: Serializer>(kind: &io::ErrorKind, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(&format_args!("{kind:?}"))
+}
+
+impl LaunchError {
+    /// Classify a failed spawn of `path`.
+    pub fn spawn(path: impl Into<String>, error: io::Error) -> Self {
+        match error.kind() {
+            io::ErrorKind::NotFound => Self::NotFound { path: path.into() },
+            io::ErrorKind::PermissionDenied => Self::PermissionDenied { path: path.into() },
+            reason => Self::SpawnFailed {
+                reason,
+                message: error.to_string(),
+            },
+        }
+    }
+}
+
+impl fmt::Display for LaunchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EntryNotFound { id } => write!(f, "Entry {id} not found"),
+            Self::ProfileNotFound { name } => write!(f, "Launch profile \"{name}\" not found"),
+            Self::NotFound { path } => write!(f, "{path} does not exist"),
+            Self::PermissionDenied { path } => write!(f, "Permission denied: {path}"),
+            Self::InvalidSteamId { app_id } => write!(f, "Invalid Steam App ID: {app_id}"),
+            Self::InvalidArgs { error } => write!(f, "Invalid launch arguments: {error}"),
+            Self::UndefinedVariable { name } => {
+                write!(f, "Environment variable {name} is not set")
+            }
+            Self::InvalidRunner { command, reason } => {
+                write!(f, "Runner `{command}` is invalid: {reason}")
+            }
+            Self::SpawnFailed { message, .. } => write!(f, "Failed to launch: {message}"),
+            Self::HookFailed { message, .. } => f.write_str(message),
+            Self::Timeout { command, secs } => {
+                write!(f, "Hook `{command}` did not finish within {secs}s")
+            }
+            Self::NestedMacro { name } => {
+                write!(f, "{name} is a macro and cannot be a step of another macro")
+            }
+            Self::MacroHasNoCommand { name } => {
+                write!(
+                    f,
+                    "{name} is a macro; it runs its steps, not a single command"
+                )
+            }
+            Self::MacroNotFound { id } => write!(f, "Macro {id} has no saved steps"),
+            Self::SteamNotStarted { secs } => {
+                write!(f, "Steam did not start within {secs}s")
+            }
+            Self::Database { message } => write!(f, "Database error: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for LaunchError {}
+
+impl From<ArgsError> for LaunchError {
+    fn from(error: ArgsError) -> Self {
+        Self::InvalidArgs { error }
+    }
+}
+
+impl From<HookError> for LaunchError {
+    fn from(error: HookError) -> Self {
+        let message = error.to_string();
+        match error {
+            HookError::TimedOut { command, secs } => Self::Timeout { command, secs },
+            HookError::Failed { command, code } => Self::HookFailed {
+                command,
+                code,
+                message,
+            },
+            HookError::Invalid { command, .. } | HookError::Spawn { command, .. } => {
+                Self::HookFailed {
+                    command,
+                    code: None,
+                    message,
+                }
+            }
+        }
+    }
+}
+
+impl From<rusqlite::Error> for LaunchError {
+    fn from(error: rusqlite::Error) -> Self {
+        Self::Database {
+            message: error.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn spawn_errors_are_classified() {
+        let missing = io::Error::from(io::ErrorKind::NotFound);
+        assert_eq!(
+            LaunchError::spawn("/games/doom", missing),
+            LaunchError::NotFound {
+                path: "/games/doom".into()
+            }
+        );
+
+        let interrupted = io::Error::new(io::ErrorKind::Interrupted, "interrupted");
+        assert!(matches!(
+            LaunchError::spawn("/games/doom", interrupted),
+            LaunchError::SpawnFailed {
+                reason: io::ErrorKind::Interrupted,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn serializes_with_kind_tag() {
+        let error = LaunchError::SpawnFailed {
+            reason: io::ErrorKind::OutOfMemory,
+            message: "out of memory".into(),
+        };
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            json!({ "kind": "spawn_failed", "reason": "OutOfMemory", "message": "out of memory" })
+        );
+
+        let timeout: LaunchError = HookError::TimedOut {
+            command: "mount-iso".into(),
+            secs: 30,
+        }
+        .into();
+        assert_eq!(
+            serde_json::to_value(&timeout).unwrap(),
+            json!({ "kind": "timeout", "command": "mount-iso", "secs": 30 })
+        );
+
+        assert_eq!(
+            serde_json::to_value(LaunchError::SteamNotStarted { secs: 30 }).unwrap(),
+            json!({ "kind": "steam_not_started", "secs": 30 })
+        );
+    }
+}
@@ -0,0 +1,277 @@
+use std::env;
+
+use super::LaunchError;
+
+/// Expand `%VAR%`, `$VAR`, `${VAR}` and a leading `~` with the current
+/// environment. See [`expand_with`].
+pub fn expand(s: &str) -> Result<String, LaunchError> {
+    expand_with(s, |name| env::var(name).ok(), home_dir().as_deref())
+}
+
+/// Expand variable references so one library works across accounts and
+/// machines:
+///
+/// - `%VAR%`, `$VAR` and `${VAR}` become the variable's value, in any
+///   syntax on any platform;
+/// - `~` at the start, alone or before a `/` or `\`, becomes the home folder;
+/// - `%%`, `$$` and a leading `~~` are a literal `%`, `$` and `~`.
+///
+/// Anything else is left as written, so a lone `%` or `$` needs no escape.
+/// A reference to an unset variable is an error rather than an empty
+/// string, which would silently point somewhere else.
+pub fn expand_with(
+    s: &str,
+    lookup: impl Fn(&str) -> Option<String>,
+    home: Option<&str>,
+) -> Result<String, LaunchError> {
+    let var = |name: &str| {
+        lookup(name).ok_or_else(|| LaunchError::UndefinedVariable { name: name.into() })
+    };
+    let chars: Vec<char> = s.chars().collect();
+    let mut out = String::with_capacity(s.len());
+    let mut i = 0;
+
+    if s.starts_with("~~") {
+        out.push('~');
+        i = 2;
+    } else if s.starts_with('~') && matches!(chars.get(1), None | Some('/' | '\\')) {
+        let home = home.ok_or_else(|| LaunchError::UndefinedVariable {
+            name: "HOME".into(),
+        })?;
+        out.push_str(home);
+        i = 1;
+    }
+
+    while i < chars.len() {
+        let c = chars[i];
+        match (c, chars.get(i + 1)) {
+            ('%', Some('%')) | ('$', Some('$')) => {
+                out.push(c);
+                i += 2;
+            }
+            ('%', _) => match name_until(&chars[i + 1..], '%', is_windows_name_char) {
+                Some(name) => {
+                    out.push_str(&var(&name)?);
+                    i += name.chars().count() + 2;
+                }
+                None => {
+                    out.push(c);
+                    i += 1;
+                }
+            },
+            ('$', Some('{')) => match name_until(&chars[i + 2..], '}', is_name_char) {
+                Some(name) => {
+                    out.push_str(&var(&name)?);
+                    i += name.chars().count() + 3;
+                }
+                None => {
+                    out.push(c);
+                    i += 1;
+                }
+            },
+            ('$', Some(&next)) if next.is_ascii_alphabetic() || next == '_' => {
+                let name: String = chars[i + 1..]
+                    .iter()
+                    .take_while(|&&c| is_name_char(c))
+                    .collect();
+                out.push_str(&var(&name)?);
+                i += name.chars().count() + 1;
+            }
+            _ => {
+                out.push(c);
+                i += 1;
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// The name before `end`, if every character up to it is valid. Names do
+/// not start with a digit, so percent-encoded text like `a%20b%20c` stays
+/// as written.
+fn name_until(chars: &[char], end: char, valid: fn(char) -> bool) -> Option<String> {
+    let len = chars.iter().position(|&c| c == end)?;
+    let name = &chars[..len];
+    let starts_well = name.first().is_some_and(|c| !c.is_ascii_digit());
+    (starts_well && name.iter().all(|&c| valid(c))).then(|| name.iter().collect())
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// Windows also has names like `ProgramFiles(x86)`.
+fn is_windows_name_char(c: char) -> bool {
+    is_name_char(c) || c == '(' || c == ')'
+}
+
+/// Escape literal text, such as a picked path or an imported argument, so
+/// it expands back to itself: `%` and `$` are doubled and a leading `~`
+/// becomes `~~`.
+pub fn literal(s: &str) -> String {
+    let escaped = s.replace('%', "%%").replace('$', "$$");
+    match escaped.strip_prefix('~') {
+        Some(rest) => format!("~~{rest}"),
+        None => escaped,
+    }
+}
+
+/// Rewrite a literal path, e.g. one picked in a file dialog, so it expands
+/// back to itself, with the home folder written as `~`.
+pub fn portable(path: &str, home: Option<&str>) -> String {
+    let escaped = literal(path);
+    let Some(home) = home
+        .map(|h| h.trim_end_matches(['/', '\\']))
+        .filter(|h| !h.is_empty())
+    else {
+        return escaped;
+    };
+    let home = home.replace('%', "%%").replace('$', "$$");
+    let under_home = escaped.get(..home.len()).is_some_and(|prefix| {
+        if cfg!(windows) {
+            prefix.eq_ignore_ascii_case(&home)
+        } else {
+            prefix == home
+        }
+    });
+    match escaped.get(home.len()..) {
+        Some(rest) if under_home && (rest.is_empty() || rest.starts_with(['/', '\\'])) => {
+            format!("~{rest}")
+        }
+        _ => escaped,
+    }
+}
+
+/// The user's home folder: `USERPROFILE` on Windows, `HOME` elsewhere.
+pub fn home_dir() -> Option<String> {
+    let var = if cfg!(windows) { "USERPROFILE" } else { "HOME" };
+    env::var(var).ok().filter(|home| !home.is_empty())
+}
+
+// --- COMMANDS ---
+
+/// A picked path escaped as written, for the add form.
+#[tauri::command]
+pub fn literal_path(path: String) -> String {
+    literal(&path)
+}
+
+/// The portable form of a picked path, for the add form.
+#[tauri::command]
+pub fn portable_path(path: String) -> String {
+    portable(&path, home_dir().as_deref())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "GAMES" => Some("/mnt/games".into()),
+            "USERPROFILE" => Some(r"C:\Users\me".into()),
+            "ProgramFiles(x86)" => Some(r"C:\Program Files (x86)".into()),
+            "EMPTY" => Some(String::new()),
+            _ => None,
+        }
+    }
+
+    fn run(s: &str) -> Result<String, LaunchError> {
+        expand_with(s, lookup, Some("/home/me"))
+    }
+
+    #[test]
+    fn expands_every_syntax() {
+        let cases = [
+            ("$GAMES/doom", "/mnt/games/doom"),
+            ("${GAMES}doom", "/mnt/gamesdoom"),
+            (r"%USERPROFILE%\Games", r"C:\Users\me\Games"),
+            (
+                r"%ProgramFiles(x86)%\Steam",
+                r"C:\Program Files (x86)\Steam",
+            ),
+            ("~/games/doom", "/home/me/games/doom"),
+            (r"~\games", r"/home/me\games"),
+            ("~", "/home/me"),
+            ("a$EMPTY.b", "a.b"),
+            ("--dir=$GAMES", "--dir=/mnt/games"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(run(input).unwrap(), expected, "input: {input:?}");
+        }
+    }
+
+    #[test]
+    fn escapes_and_literals() {
+        let cases = [
+            ("100%% done", "100% done"),
+            ("$$GAMES", "$GAMES"),
+            ("%%GAMES%%", "%GAMES%"),
+            ("~~/x", "~/x"),
+            ("~user/x", "~user/x"),
+            ("a~/b", "a~/b"),
+            ("100% done", "100% done"),
+            ("50% off 20%", "50% off 20%"),
+            ("cost: $5", "cost: $5"),
+            ("${not closed", "${not closed"),
+            ("trailing $", "trailing $"),
+            ("https://x.org/a%20b%20c", "https://x.org/a%20b%20c"),
+            (r"C:\Games\game.exe", r"C:\Games\game.exe"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(run(input).unwrap(), expected, "input: {input:?}");
+        }
+    }
+
+    #[test]
+    fn unset_variables_are_errors() {
+        assert_eq!(
+            run("$NOPE/x"),
+            Err(LaunchError::UndefinedVariable {
+                name: "NOPE".into()
+            })
+        );
+        assert!(run("%NOPE%").is_err());
+        assert!(run("${NOPE}").is_err());
+        assert_eq!(
+            expand_with("~/x", lookup, None),
+            Err(LaunchError::UndefinedVariable {
+                name: "HOME".into()
+            })
+        );
+    }
+
+    #[test]
+    fn portable_paths_round_trip() {
+        let home = Some("/home/me");
+        let cases = [
+            ("/home/me/games/doom", "~/games/doom"),
+            ("/home/me", "~"),
+            ("/home/meg/doom", "/home/meg/doom"),
+            ("/opt/100%/$x", "/opt/100%%/$$x"),
+            ("~odd/file", "~~odd/file"),
+        ];
+        for (path, expected) in cases {
+            let portable = portable(path, home);
+            assert_eq!(portable, expected, "path: {path:?}");
+            assert_eq!(expand_with(&portable, lookup, home).unwrap(), path);
+        }
+        assert_eq!(portable("/home/me/x", None), "/home/me/x");
+    }
+
+    #[test]
+    fn literals_round_trip() {
+        for text in [
+            "/opt/$HOME/100%",
+            "%GAMES%",
+            "${GAMES}",
+            "~",
+            "~/x",
+            "plain",
+        ] {
+            let escaped = literal(text);
+            assert_eq!(run(&escaped).unwrap(), text, "escaped: {escaped:?}");
+        }
+        assert_eq!(literal("~/$x"), "~~/$$x");
+    }
+}
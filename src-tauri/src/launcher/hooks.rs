@@ -0,0 +1,214 @@
+use std::fmt;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use super::args;
+use super::control;
+use super::platform::LaunchCommand;
+
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// A command run before or after an entry. The command line is split with
+/// the same tokenizer as launch arguments; the first word is the program.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Hook {
+    pub command: String,
+    #[serde(default = "default_timeout")]
+    pub timeout_secs: u64,
+}
+
+fn default_timeout() -> u64 {
+    DEFAULT_TIMEOUT_SECS
+}
+
+/// The `hooks` column of an entry.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Hooks {
+    /// Run in order before the entry starts. Any failure aborts the launch.
+    #[serde(default)]
+    pub pre_launch: Vec<Hook>,
+    /// Run in order after a tracked entry exits. A failure stops the chain.
+    #[serde(default)]
+    pub post_exit: Vec<Hook>,
+}
+
+/// Why a hook did not complete successfully.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HookError {
+    Invalid { command: String, reason: String },
+    Spawn { command: String, reason: String },
+    Failed { command: String, code: Option<i32> },
+    TimedOut { command: String, secs: u64 },
+}
+
+impl fmt::Display for HookError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Invalid { command, reason } => write!(f, "Hook `{command}` is invalid: {reason}"),
+            Self::Spawn { command, reason } => {
+                write!(f, "Hook `{command}` could not start: {reason}")
+            }
+            Self::Failed {
+                command,
+                code: Some(code),
+            } => write!(f, "Hook `{command}` failed with exit code {code}"),
+            Self::Failed {
+                command,
+                code: None,
+            } => {
+                write!(f, "Hook `{command}` was terminated")
+            }
+            Self::TimedOut { command, secs } => {
+                write!(f, "Hook `{command}` did not finish within {secs}s")
+            }
+        }
+    }
+}
+
+impl std::error::Error for HookError {}
+
+/// Build the process for a hook. It inherits the entry's working directory
+/// and environment, so a hook sees the same context as the entry itself.
+pub fn hook_command(hook: &Hook, context: &LaunchCommand) -> Result<LaunchCommand, HookError> {
+    let invalid = |reason: String| HookError::Invalid {
+        command: hook.command.clone(),
+        reason,
+    };
+
+    let mut words = args::split(&hook.command)
+        .map_err(|e| invalid(e.to_string()))?
+        .into_iter();
+    let program = words
+        .next()
+        .ok_or_else(|| invalid("empty command".into()))?;
+
+    let mut command = LaunchCommand::new(program).args(words);
+    command.current_dir = context.current_dir.clone();
+    command.env = context.env.clone();
+    Ok(command)
+}
+
+/// Run one hook to completion, killing it if it outlives its timeout.
+pub fn run_hook(hook: &Hook, context: &LaunchCommand) -> Result<(), HookError> {
+    let command = hook_command(hook, context)?;
+    let mut child = super::spawn(&command).map_err(|e| HookError::Spawn {
+        command: hook.command.clone(),
+        reason: e.to_string(),
+    })?;
+
+    let deadline = Instant::now() + Duration::from_secs(hook.timeout_secs);
+    loop {
+        let status = child.try_wait().map_err(|e| HookError::Spawn {
+            command: hook.command.clone(),
+            reason: e.to_string(),
+        })?;
+
+        match status {
+            Some(status) if status.success() => return Ok(()),
+            Some(status) => {
+                return Err(HookError::Failed {
+                    command: hook.command.clone(),
+                    code: status.code(),
+                })
+            }
+            None if Instant::now() >= deadline => {
+                // The hook leads its own process group; take down anything
+                // it started along with it.
+                control::kill_tree(child.id());
+                let _ = child.wait();
+                return Err(HookError::TimedOut {
+                    command: hook.command.clone(),
+                    secs: hook.timeout_secs,
+                });
+            }
+            None => thread::sleep(POLL_INTERVAL),
+        }
+    }
+}
+
+/// Run hooks in order, stopping at the first one that fails.
+pub fn run_all(hooks: &[Hook], context: &LaunchCommand) -> Result<(), HookError> {
+    hooks.iter().try_for_each(|hook| run_hook(hook, context))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn hook(command: &str, timeout_secs: u64) -> Hook {
+        Hook {
+            command: command.into(),
+            timeout_secs,
+        }
+    }
+
+    #[test]
+    fn hooks_inherit_entry_context() {
+        let mut context = LaunchCommand::new("/games/doom/doom");
+        context.current_dir = Some("/games/doom".into());
+        context.env.insert("WINEPREFIX".into(), "/pfx".into());
+
+        let command = hook_command(&hook(r#"mount-iso "/isos/Doom 3.iso""#, 5), &context).unwrap();
+        assert_eq!(command.program, "mount-iso");
+        assert_eq!(command.args, ["/isos/Doom 3.iso"]);
+        assert_eq!(command.current_dir, context.current_dir);
+        assert_eq!(command.env, context.env);
+    }
+
+    #[test]
+    fn run_all_stops_at_first_failure() {
+        let context = LaunchCommand::new("unused");
+        let hooks = [
+            hook("true", 5),
+            hook("sh -c 'exit 3'", 5),
+            hook("this-hook-must-not-run", 5),
+        ];
+
+        assert_eq!(
+            run_all(&hooks, &context),
+            Err(HookError::Failed {
+                command: "sh -c 'exit 3'".into(),
+                code: Some(3),
+            })
+        );
+    }
+
+    #[test]
+    fn slow_hooks_time_out() {
+        let context = LaunchCommand::new("unused");
+        let started = Instant::now();
+
+        assert!(matches!(
+            run_hook(&hook("sleep 5", 0), &context),
+            Err(HookError::TimedOut { secs: 0, .. })
+        ));
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        // Whatever the hook started goes down with it.
+        let pgid_file = std::env::temp_dir().join(format!("kscope-hook-{}", std::process::id()));
+        let command = format!("sh -c 'echo $$ > {}; sleep 30 & wait'", pgid_file.display());
+        assert!(matches!(
+            run_hook(&hook(&command, 1), &context),
+            Err(HookError::TimedOut { secs: 1, .. })
+        ));
+        let pgid = std::fs::read_to_string(&pgid_file).unwrap();
+        let _ = std::fs::remove_file(&pgid_file);
+        assert!(!control::group_alive(pgid.trim().parse().unwrap()));
+    }
+
+    #[test]
+    fn empty_and_malformed_hooks_are_invalid() {
+        let context = LaunchCommand::new("unused");
+        assert!(matches!(
+            hook_command(&hook("  ", 5), &context),
+            Err(HookError::Invalid { .. })
+        ));
+        assert!(matches!(
+            hook_command(&hook("echo 'open", 5), &context),
+            Err(HookError::Invalid { .. })
+        ));
+    }
+}
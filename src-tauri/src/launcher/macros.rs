@@ -0,0 +1,528 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+
+use super::{launch_program, LaunchError, LaunchResult, LaunchType};
+use crate::db::Database;
+
+/// A macro is an entry with the `macro` launch type: it is listed,
+/// categorised and launched like any other entry, and this table holds what
+/// it runs. Everything goes with the macro entry, and a step goes with the
+/// entry it points at.
+pub const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS macros (
+        entry_id INTEGER PRIMARY KEY REFERENCES entries(id) ON DELETE CASCADE,
+        mode TEXT NOT NULL DEFAULT 'sequential',
+        on_failure TEXT NOT NULL DEFAULT 'stop'
+    );
+    CREATE TABLE IF NOT EXISTS macro_steps (
+        macro_id INTEGER NOT NULL REFERENCES macros(entry_id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        entry_id INTEGER NOT NULL REFERENCES entries(id) ON DELETE CASCADE,
+        profile TEXT,
+        delay_ms INTEGER NOT NULL DEFAULT 0,
+        PRIMARY KEY (macro_id, position)
+    );
+";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MacroMode {
+    /// One step after the other; each delay is counted from the previous
+    /// step's launch.
+    Sequential,
+    /// All steps at once; each delay is counted from the start of the macro.
+    Parallel,
+}
+
+impl MacroMode {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Sequential => "sequential",
+            Self::Parallel => "parallel",
+        }
+    }
+}
+
+/// What happens to the remaining steps when one fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailurePolicy {
+    /// Skip every step that has not started yet.
+    Stop,
+    Continue,
+}
+
+impl FailurePolicy {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Stop => "stop",
+            Self::Continue => "continue",
+        }
+    }
+}
+
+/// Both enums are stored as their serde names.
+macro_rules! text_column {
+    ($ty:ty, $($variant:ident),+) => {
+        impl ToSql for $ty {
+            fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+                Ok(self.as_str().into())
+            }
+        }
+
+        impl FromSql for $ty {
+            fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+                let text = value.as_str()?;
+                [$(Self::$variant),+]
+                    .into_iter()
+                    .find(|v| v.as_str() == text)
+                    .ok_or_else(|| FromSqlError::Other(format!("Unknown value: {text}").into()))
+            }
+        }
+    };
+}
+
+text_column!(MacroMode, Sequential, Parallel);
+text_column!(FailurePolicy, Stop, Continue);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MacroStep {
+    pub entry_id: i64,
+    /// Launch profile name; `None` uses the entry's default.
+    #[serde(default)]
+    pub profile: Option<String>,
+    #[serde(default)]
+    pub delay_ms: u64,
+}
+
+/// What a macro entry runs. Its name, category and art are on the entry.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Macro {
+    pub mode: MacroMode,
+    pub on_failure: FailurePolicy,
+    /// In launch order.
+    pub steps: Vec<MacroStep>,
+}
+
+/// How one step of a macro went.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum StepOutcome {
+    Launched {
+        pid: u32,
+    },
+    AlreadyRunning {
+        pid: u32,
+    },
+    Failed {
+        error: LaunchError,
+    },
+    /// Not started because an earlier step failed under `FailurePolicy::Stop`.
+    Skipped,
+}
+
+impl From<Result<LaunchResult, LaunchError>> for StepOutcome {
+    fn from(result: Result<LaunchResult, LaunchError>) -> Self {
+        match result {
+            Ok(LaunchResult::Launched { pid, .. }) => Self::Launched { pid },
+            Ok(LaunchResult::AlreadyRunning { pid, .. }) => Self::AlreadyRunning { pid },
+            Ok(LaunchResult::Macro { .. }) => {
+                unreachable!("steps are started with launch_program, which rejects macros")
+            }
+            Err(error) => Self::Failed { error },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StepReport {
+    pub entry_id: i64,
+    #[serde(flatten)]
+    pub outcome: StepOutcome,
+}
+
+// --- STORAGE ---
+
+fn steps(conn: &Connection, macro_id: i64) -> rusqlite::Result<Vec<MacroStep>> {
+    let mut stmt = conn.prepare(
+        "SELECT entry_id, profile, delay_ms FROM macro_steps WHERE macro_id = ?1 ORDER BY position",
+    )?;
+    let rows = stmt.query_map(params![macro_id], |row| {
+        Ok(MacroStep {
+            entry_id: row.get("entry_id")?,
+            profile: row.get("profile")?,
+            delay_ms: row.get("delay_ms")?,
+        })
+    })?;
+    rows.collect()
+}
+
+/// The steps of a macro entry; `None` if `entry_id` is not a saved macro.
+pub fn get(conn: &Connection, entry_id: i64) -> rusqlite::Result<Option<Macro>> {
+    let found = conn
+        .query_row(
+            "SELECT mode, on_failure FROM macros WHERE entry_id = ?1",
+            params![entry_id],
+            |row| {
+                Ok(Macro {
+                    mode: row.get("mode")?,
+                    on_failure: row.get("on_failure")?,
+                    steps: Vec::new(),
+                })
+            },
+        )
+        .optional()?;
+    found
+        .map(|mut m| {
+            m.steps = steps(conn, entry_id)?;
+            Ok(m)
+        })
+        .transpose()
+}
+
+/// Replace what the macro entry `entry_id` runs. Steps must point at
+/// entries that are not macros themselves. Call it inside the transaction
+/// that saves the entry, so a rejected macro leaves no entry behind.
+pub fn save(conn: &Connection, entry_id: i64, m: &Macro) -> Result<(), String> {
+    if m.steps.is_empty() {
+        return Err("A macro needs at least one step".into());
+    }
+    for step in &m.steps {
+        let target: Option<(String, LaunchType)> = conn
+            .query_row(
+                "SELECT name, launch_type FROM entries WHERE id = ?1",
+                params![step.entry_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .map_err(|e| e.to_string())?;
+        match target {
+            None => return Err(format!("Entry {} not found", step.entry_id)),
+            Some((name, LaunchType::Macro)) => {
+                return Err(format!(
+                    "{name} is a macro; a macro cannot be a step of another macro"
+                ))
+            }
+            Some(_) => {}
+        }
+    }
+
+    conn.execute(
+        "INSERT INTO macros (entry_id, mode, on_failure) VALUES (?1, ?2, ?3)
+         ON CONFLICT (entry_id) DO UPDATE SET mode = ?2, on_failure = ?3",
+        params![entry_id, m.mode, m.on_failure],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute(
+        "DELETE FROM macro_steps WHERE macro_id = ?1",
+        params![entry_id],
+    )
+    .map_err(|e| e.to_string())?;
+    for (position, step) in m.steps.iter().enumerate() {
+        let delay = i64::try_from(step.delay_ms).map_err(|e| e.to_string())?;
+        conn.execute(
+            "INSERT INTO macro_steps (macro_id, position, entry_id, profile, delay_ms)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                entry_id,
+                position as i64,
+                step.entry_id,
+                step.profile,
+                delay
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Drop the steps of an entry that is no longer a macro.
+pub fn remove(conn: &Connection, entry_id: i64) -> rusqlite::Result<()> {
+    conn.execute("DELETE FROM macros WHERE entry_id = ?1", params![entry_id])?;
+    Ok(())
+}
+
+// --- RUNNING ---
+
+/// Run steps with the given launcher, returning one outcome per step in step
+/// order.
+pub fn run<F>(
+    steps: &[MacroStep],
+    mode: MacroMode,
+    on_failure: FailurePolicy,
+    launch_step: F,
+) -> Vec<StepReport>
+where
+    F: Fn(&MacroStep) -> Result<LaunchResult, LaunchError> + Sync,
+{
+    let failed = AtomicBool::new(false);
+    let run_step = |step: &MacroStep| {
+        thread::sleep(Duration::from_millis(step.delay_ms));
+        let outcome = if on_failure == FailurePolicy::Stop && failed.load(Ordering::SeqCst) {
+            StepOutcome::Skipped
+        } else {
+            StepOutcome::from(launch_step(step))
+        };
+        if matches!(outcome, StepOutcome::Failed { .. }) {
+            failed.store(true, Ordering::SeqCst);
+        }
+        StepReport {
+            entry_id: step.entry_id,
+            outcome,
+        }
+    };
+
+    match mode {
+        MacroMode::Sequential => steps
+            .iter()
+            .map(|step| {
+                if on_failure == FailurePolicy::Stop && failed.load(Ordering::SeqCst) {
+                    // Do not wait out the delay of a step that will not run.
+                    StepReport {
+                        entry_id: step.entry_id,
+                        outcome: StepOutcome::Skipped,
+                    }
+                } else {
+                    run_step(step)
+                }
+            })
+            .collect(),
+        MacroMode::Parallel => thread::scope(|scope| {
+            let handles: Vec<_> = steps
+                .iter()
+                .map(|step| scope.spawn(|| run_step(step)))
+                .collect();
+            handles
+                .into_iter()
+                .zip(steps)
+                .map(|(handle, step)| {
+                    handle.join().unwrap_or(StepReport {
+                        entry_id: step.entry_id,
+                        outcome: StepOutcome::Skipped,
+                    })
+                })
+                .collect()
+        }),
+    }
+}
+
+/// Launch every step of the macro entry `entry_id` with the same rules as
+/// `launch_entry`. Single-instance entries that are already running are
+/// reported, not relaunched, and do not count as failures.
+pub fn launch(app: &AppHandle, entry_id: i64) -> Result<LaunchResult, LaunchError> {
+    let m = load(&app.state::<Database>().conn(), entry_id)?;
+    let steps = run(&m.steps, m.mode, m.on_failure, |step| {
+        launch_program(app, step.entry_id, step.profile.as_deref(), false)
+    });
+    Ok(LaunchResult::Macro { entry_id, steps })
+}
+
+/// The steps of a macro entry about to run; `MacroNotFound` if none are
+/// saved, rather than a launch that silently does nothing.
+fn load(conn: &Connection, entry_id: i64) -> Result<Macro, LaunchError> {
+    get(conn, entry_id)?.ok_or(LaunchError::MacroNotFound { id: entry_id })
+}
+
+// --- COMMANDS ---
+
+/// The steps of a macro entry, for the edit form.
+#[tauri::command]
+pub fn get_macro(db: State<'_, Database>, entry_id: i64) -> Result<Macro, String> {
+    get(&db.conn(), entry_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Entry {entry_id} is not a macro"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    /// Entry 4 is the macro under test; entry 5 is another macro.
+    const MACRO: i64 = 4;
+
+    fn conn() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE entries (id INTEGER PRIMARY KEY, name TEXT, launch_type TEXT);
+             INSERT INTO entries VALUES
+                (1, 'Game', 'exe'), (2, 'Voice', 'exe'), (3, 'Wiki', 'url'),
+                (4, 'Session', 'macro'), (5, 'Other session', 'macro');",
+        )
+        .unwrap();
+        conn.execute_batch(SCHEMA).unwrap();
+        conn
+    }
+
+    fn step(entry_id: i64) -> MacroStep {
+        MacroStep {
+            entry_id,
+            profile: None,
+            delay_ms: 0,
+        }
+    }
+
+    /// Launches everything except entry 2, recording the order of calls.
+    fn fake_launch(
+        calls: &Mutex<Vec<i64>>,
+    ) -> impl Fn(&MacroStep) -> Result<LaunchResult, LaunchError> + Sync + '_ {
+        move |step| {
+            calls.lock().unwrap().push(step.entry_id);
+            if step.entry_id == 2 {
+                Err(LaunchError::EntryNotFound { id: 2 })
+            } else {
+                Ok(LaunchResult::Launched {
+                    entry_id: step.entry_id,
+                    pid: 100 + step.entry_id as u32,
+                })
+            }
+        }
+    }
+
+    fn outcomes(reports: &[StepReport]) -> Vec<&StepOutcome> {
+        reports.iter().map(|r| &r.outcome).collect()
+    }
+
+    #[test]
+    fn unsaved_macros_do_not_launch() {
+        let conn = conn();
+        assert_eq!(
+            load(&conn, MACRO).unwrap_err(),
+            LaunchError::MacroNotFound { id: MACRO }
+        );
+        let m = Macro {
+            mode: MacroMode::Sequential,
+            on_failure: FailurePolicy::Stop,
+            steps: vec![step(1)],
+        };
+        save(&conn, MACRO, &m).unwrap();
+        assert_eq!(load(&conn, MACRO).unwrap(), m);
+    }
+
+    #[test]
+    fn save_and_load_steps_in_order() {
+        let conn = conn();
+        let mut m = Macro {
+            mode: MacroMode::Parallel,
+            on_failure: FailurePolicy::Continue,
+            steps: vec![
+                step(3),
+                MacroStep {
+                    entry_id: 1,
+                    profile: Some("Vulkan".into()),
+                    delay_ms: 1500,
+                },
+            ],
+        };
+        save(&conn, MACRO, &m).unwrap();
+        assert_eq!(get(&conn, MACRO).unwrap(), Some(m.clone()));
+
+        m.steps.pop();
+        m.mode = MacroMode::Sequential;
+        save(&conn, MACRO, &m).unwrap();
+        assert_eq!(get(&conn, MACRO).unwrap(), Some(m));
+
+        conn.execute("DELETE FROM entries WHERE id = 3", [])
+            .unwrap();
+        assert!(get(&conn, MACRO).unwrap().unwrap().steps.is_empty());
+
+        conn.execute("DELETE FROM entries WHERE id = ?1", params![MACRO])
+            .unwrap();
+        assert_eq!(get(&conn, MACRO).unwrap(), None);
+    }
+
+    #[test]
+    fn save_rejects_bad_steps() {
+        let conn = conn();
+        let m = Macro {
+            mode: MacroMode::Sequential,
+            on_failure: FailurePolicy::Stop,
+            steps: vec![step(42)],
+        };
+        assert_eq!(save(&conn, MACRO, &m), Err("Entry 42 not found".into()));
+        assert!(save(
+            &conn,
+            MACRO,
+            &Macro {
+                steps: vec![],
+                ..m.clone()
+            }
+        )
+        .is_err());
+        assert!(save(
+            &conn,
+            MACRO,
+            &Macro {
+                steps: vec![step(1), step(5)],
+                ..m
+            }
+        )
+        .unwrap_err()
+        .starts_with("Other session is a macro"));
+        assert_eq!(get(&conn, MACRO).unwrap(), None);
+    }
+
+    #[test]
+    fn sequential_stop_skips_the_rest() {
+        let calls = Mutex::new(Vec::new());
+        let reports = run(
+            &[step(1), step(2), step(3)],
+            MacroMode::Sequential,
+            FailurePolicy::Stop,
+            fake_launch(&calls),
+        );
+        assert!(matches!(
+            outcomes(&reports)[..],
+            [
+                StepOutcome::Launched { pid: 101 },
+                StepOutcome::Failed { .. },
+                StepOutcome::Skipped
+            ]
+        ));
+        assert_eq!(*calls.lock().unwrap(), [1, 2]);
+    }
+
+    #[test]
+    fn sequential_continue_runs_everything() {
+        let calls = Mutex::new(Vec::new());
+        let reports = run(
+            &[step(1), step(2), step(3)],
+            MacroMode::Sequential,
+            FailurePolicy::Continue,
+            fake_launch(&calls),
+        );
+        assert!(matches!(
+            outcomes(&reports)[..],
+            [
+                StepOutcome::Launched { .. },
+                StepOutcome::Failed { .. },
+                StepOutcome::Launched { pid: 103 }
+            ]
+        ));
+        assert_eq!(*calls.lock().unwrap(), [1, 2, 3]);
+    }
+
+    #[test]
+    fn parallel_stop_skips_steps_that_had_not_started() {
+        let calls = Mutex::new(Vec::new());
+        let mut late = step(3);
+        late.delay_ms = 200;
+        let reports = run(
+            &[step(2), step(1), late],
+            MacroMode::Parallel,
+            FailurePolicy::Stop,
+            fake_launch(&calls),
+        );
+        assert_eq!(reports.len(), 3);
+        assert!(matches!(reports[0].outcome, StepOutcome::Failed { .. }));
+        assert_eq!(reports[2].outcome, StepOutcome::Skipped);
+        assert!(!calls.lock().unwrap().contains(&3));
+    }
+}
@@ -0,0 +1,514 @@
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Child;
+use std::str::FromStr;
+
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+
+use crate::db::{json_column, unix_now, Database};
+use crate::playtime;
+
+pub mod args;
+pub mod control;
+pub mod error;
+pub mod expand;
+pub mod hooks;
+pub mod macros;
+pub mod platform;
+pub mod preview;
+pub mod profiles;
+pub mod runners;
+pub mod steam;
+pub mod tracker;
+
+use args::ArgsError;
+pub use error::LaunchError;
+use hooks::Hooks;
+use macros::StepReport;
+use platform::LaunchCommand;
+use runners::Runner;
+use tracker::{ProcessRegistry, RunningEntry};
+
+/// How an entry is started. Mirrors the `launch_type` column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LaunchType {
+    Steam,
+    Exe,
+    Url,
+    Bat,
+    /// Runs other entries; see [`macros`].
+    Macro,
+}
+
+impl LaunchType {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Steam => "steam",
+            Self::Exe => "exe",
+            Self::Url => "url",
+            Self::Bat => "bat",
+            Self::Macro => "macro",
+        }
+    }
+}
+
+impl ToSql for LaunchType {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(self.as_str().into())
+    }
+}
+
+impl FromSql for LaunchType {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        value
+            .as_str()?
+            .parse()
+            .map_err(|e: String| FromSqlError::Other(e.into()))
+    }
+}
+
+impl FromStr for LaunchType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "steam" => Ok(Self::Steam),
+            "exe" => Ok(Self::Exe),
+            "url" => Ok(Self::Url),
+            "bat" => Ok(Self::Bat),
+            "macro" => Ok(Self::Macro),
+            other => Err(format!("Unknown launch type: {other}")),
+        }
+    }
+}
+
+/// The part of an `entries` row the launcher needs.
+#[derive(Debug, Clone)]
+pub struct LaunchEntry {
+    pub id: i64,
+    pub name: String,
+    pub launch_type: LaunchType,
+    pub launch_data: String,
+    pub launch_args: String,
+    /// Empty means "the folder the executable or script lives in".
+    pub working_dir: String,
+    /// Variables set on top of K-Scope's own environment.
+    pub env: BTreeMap<String, String>,
+    pub hooks: Hooks,
+    /// Refuse to start a second copy while a tracked one is alive.
+    pub single_instance: bool,
+    /// Runner chain for executables and scripts, outermost first. `None`
+    /// means "use the global chain" until `prepare` resolves it.
+    pub runners: Option<Vec<Runner>>,
+    /// Steam only: start the client silently before handing it the URL,
+    /// instead of letting the URL bring up its main window.
+    pub start_steam_silently: bool,
+}
+
+/// What `launch_entry` did.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum LaunchResult {
+    Launched {
+        entry_id: i64,
+        pid: u32,
+    },
+    /// The entry is single-instance and a tracked copy is still alive.
+    /// Nothing was spawned; `pid` is the running copy.
+    AlreadyRunning {
+        entry_id: i64,
+        pid: u32,
+    },
+    /// The entry is a macro; one report per step, in step order.
+    Macro {
+        entry_id: i64,
+        steps: Vec<StepReport>,
+    },
+}
+
+/// Load a single entry by id.
+pub fn load_entry(conn: &Connection, id: i64) -> Result<LaunchEntry, LaunchError> {
+    conn.query_row(
+        "SELECT id, name, launch_type, launch_data,
+                COALESCE(launch_args, '') AS launch_args,
+                COALESCE(working_dir, '') AS working_dir,
+                COALESCE(env, '{}') AS env,
+                COALESCE(hooks, '{}') AS hooks,
+                COALESCE(single_instance, 0) AS single_instance,
+                COALESCE(start_steam_silently, 0) AS start_steam_silently,
+                COALESCE(runners, 'null') AS runners
+         FROM entries WHERE id = ?1",
+        params![id],
+        |row| {
+            Ok(LaunchEntry {
+                id: row.get("id")?,
+                name: row.get("name")?,
+                launch_type: row.get("launch_type")?,
+                launch_data: row.get("launch_data")?,
+                launch_args: row.get("launch_args")?,
+                working_dir: row.get("working_dir")?,
+                env: json_column(row, "env")?,
+                hooks: json_column(row, "hooks")?,
+                single_instance: row.get("single_instance")?,
+                start_steam_silently: row.get("start_steam_silently")?,
+                runners: json_column(row, "runners")?,
+            })
+        },
+    )
+    .optional()?
+    .ok_or(LaunchError::EntryNotFound { id })
+}
+
+/// Build the command line for an entry on the current platform, without
+/// spawning it. Variables in the target path, working directory, each
+/// argument and the env values of the entry and its runners are expanded
+/// here (see [`expand::expand_with`]); Steam ids and URLs are used as stored.
+pub fn build_command(entry: &LaunchEntry) -> Result<LaunchCommand, LaunchError> {
+    if entry.launch_type == LaunchType::Macro {
+        return Err(LaunchError::NestedMacro {
+            name: entry.name.clone(),
+        });
+    }
+    let local = matches!(entry.launch_type, LaunchType::Exe | LaunchType::Bat);
+    let target = if local {
+        expand::expand(entry.launch_data.trim())?
+    } else {
+        entry.launch_data.trim().to_string()
+    };
+    let target = target.as_str();
+
+    if entry.launch_type == LaunchType::Steam
+        && (target.is_empty() || !target.chars().all(|c| c.is_ascii_digit()))
+    {
+        return Err(LaunchError::InvalidSteamId {
+            app_id: target.to_string(),
+        });
+    }
+
+    let args = args::split(&entry.launch_args)?
+        .iter()
+        .map(|arg| expand::expand(arg))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut command = platform::command_for(&platform::Current, entry.launch_type, target, &args);
+
+    if local {
+        let configured = expand::expand(entry.working_dir.trim())?;
+        command.current_dir = working_dir(&configured, target);
+        command.env = expand_env(&entry.env)?;
+        let chain = entry
+            .runners
+            .iter()
+            .flatten()
+            .map(|runner| {
+                Ok(Runner {
+                    command: runner.command.clone(),
+                    env: expand_env(&runner.env)?,
+                })
+            })
+            .collect::<Result<Vec<_>, LaunchError>>()?;
+        command = runners::wrap(command, &chain)?;
+    }
+
+    Ok(command)
+}
+
+fn expand_env(env: &BTreeMap<String, String>) -> Result<BTreeMap<String, String>, LaunchError> {
+    env.iter()
+        .map(|(name, value)| Ok((name.clone(), expand::expand(value)?)))
+        .collect()
+}
+
+/// An entry with its profile applied and its command built.
+pub struct PreparedLaunch {
+    pub entry: LaunchEntry,
+    /// Name of the applied profile, if any.
+    pub profile: Option<String>,
+    pub command: LaunchCommand,
+}
+
+/// Load an entry, apply the named (or default) profile, resolve its runner
+/// chain and build its command. `launch_entry` and `preview_launch` both
+/// start here.
+pub fn prepare(
+    conn: &Connection,
+    id: i64,
+    profile: Option<&str>,
+) -> Result<PreparedLaunch, LaunchError> {
+    let mut entry = load_entry(conn, id)?;
+    let profile = profiles::resolve(conn, id, profile)?;
+    if let Some(profile) = &profile {
+        profile.apply(&mut entry);
+    }
+    if entry.runners.is_none() {
+        entry.runners = Some(runners::global(conn)?);
+    }
+    let command = build_command(&entry)?;
+    Ok(PreparedLaunch {
+        entry,
+        profile: profile.map(|p| p.name),
+        command,
+    })
+}
+
+/// The configured working directory, or the target's parent folder. Many
+/// games only find their data files when started from their own folder.
+fn working_dir(configured: &str, target: &str) -> Option<PathBuf> {
+    if !configured.is_empty() {
+        return Some(PathBuf::from(configured));
+    }
+    Path::new(target)
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .map(Path::to_path_buf)
+}
+
+/// Fail with `NotFound` when an executable or script given by absolute path
+/// is missing. Bare names are left to the `PATH` lookup at spawn time.
+fn check_target(entry: &LaunchEntry) -> Result<(), LaunchError> {
+    let local = matches!(entry.launch_type, LaunchType::Exe | LaunchType::Bat);
+    if !local {
+        return Ok(());
+    }
+    let target = expand::expand(entry.launch_data.trim())?;
+    let target = Path::new(&target);
+    if target.is_absolute() && !target.exists() {
+        return Err(LaunchError::NotFound {
+            path: target.display().to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Spawn a built command. Entries and their hooks all start here.
+pub fn spawn(command: &LaunchCommand) -> io::Result<Child> {
+    command.to_command().spawn()
+}
+
+/// Run the pre-launch hooks and start Steam if asked to, then check the
+/// target. The check comes last, since a hook may mount the drive the
+/// target is on or unpack it.
+fn before_spawn(entry: &LaunchEntry, command: &LaunchCommand) -> Result<(), LaunchError> {
+    hooks::run_all(&entry.hooks.pre_launch, command)?;
+    if entry.launch_type == LaunchType::Steam && entry.start_steam_silently {
+        steam::ensure_running()?;
+    }
+    check_target(entry)
+}
+
+/// Launch an entry by id. Macros run their steps (see [`macros::launch`]);
+/// everything else goes through [`launch_program`].
+pub fn launch(
+    app: &AppHandle,
+    id: i64,
+    profile: Option<&str>,
+    force: bool,
+) -> Result<LaunchResult, LaunchError> {
+    let launch_type = load_entry(&app.state::<Database>().conn(), id)?.launch_type;
+    if launch_type == LaunchType::Macro {
+        return macros::launch(app, id);
+    }
+    launch_program(app, id, profile, force)
+}
+
+/// Launch a single program by entry id with the named profile, or the entry's default
+/// profile when `profile` is `None`. Pre-launch hooks run first and can abort the
+/// launch. Executables and scripts are tracked until they exit, get a
+/// playtime session and run their post-exit hooks; Steam and URL launches
+/// only hand off to another program, so their helper process is reaped but
+/// not tracked.
+///
+/// A single-instance entry whose tracked process is still alive returns
+/// `AlreadyRunning` instead of spawning, unless `force` is set. Macros are
+/// refused with `NestedMacro`.
+fn launch_program(
+    app: &AppHandle,
+    id: i64,
+    profile: Option<&str>,
+    force: bool,
+) -> Result<LaunchResult, LaunchError> {
+    let db = app.state::<Database>();
+    let processes = app.state::<ProcessRegistry>();
+    let PreparedLaunch { entry, command, .. } = prepare(&db.conn(), id, profile)?;
+
+    let launch_lock = processes.launch_lock(entry.id);
+    let _launching = tracker::lock(&launch_lock);
+
+    if entry.single_instance && !force {
+        if let Some(running) = processes.find(entry.id) {
+            return Ok(LaunchResult::AlreadyRunning {
+                entry_id: entry.id,
+                pid: running.pid,
+            });
+        }
+    }
+
+    before_spawn(&entry, &command)?;
+    let child = spawn(&command).map_err(|e| LaunchError::spawn(&command.program, e))?;
+    let pid = child.id();
+
+    match entry.launch_type {
+        LaunchType::Exe | LaunchType::Bat => {
+            let session = playtime::start_session(&db.conn(), entry.id, unix_now())?;
+            let handle = app.clone();
+            let post_exit = entry.hooks.post_exit;
+            processes.track(app.clone(), entry.id, child, move |_| {
+                let db = handle.state::<Database>();
+                let _ = playtime::end_session(&db.conn(), session, unix_now());
+                if let Err(e) = hooks::run_all(&post_exit, &command) {
+                    log::error!("post-exit hooks of {} failed: {e}", entry.name);
+                }
+            });
+        }
+        LaunchType::Steam | LaunchType::Url | LaunchType::Macro => tracker::reap(child),
+    }
+
+    Ok(LaunchResult::Launched {
+        entry_id: entry.id,
+        pid,
+    })
+}
+
+// --- COMMANDS ---
+
+/// See [`launch`]. Runs off the main thread, since hooks may take a while.
+#[tauri::command(async)]
+pub fn launch_entry(
+    app: AppHandle,
+    id: i64,
+    profile: Option<String>,
+    force: Option<bool>,
+) -> Result<LaunchResult, LaunchError> {
+    launch(&app, id, profile.as_deref(), force.unwrap_or(false))
+}
+
+/// Split launch arguments the way `launch_entry` will, so the form can
+/// reject malformed input before it is saved.
+#[tauri::command]
+pub fn parse_launch_args(args: String) -> Result<Vec<String>, ArgsError> {
+    args::split(&args)
+}
+
+/// Tracked processes that are alive right now.
+#[tauri::command]
+pub fn running_entries(processes: State<'_, ProcessRegistry>) -> Vec<RunningEntry> {
+    processes.running()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exe(path: &str) -> LaunchEntry {
+        LaunchEntry {
+            id: 1,
+            name: "Game".into(),
+            launch_type: LaunchType::Exe,
+            launch_data: path.into(),
+            launch_args: String::new(),
+            working_dir: String::new(),
+            env: BTreeMap::new(),
+            hooks: Hooks::default(),
+            single_instance: false,
+            start_steam_silently: false,
+            runners: None,
+        }
+    }
+
+    #[test]
+    fn working_dir_defaults_to_target_folder() {
+        let command = build_command(&exe("/games/doom/doom.x86_64")).unwrap();
+        assert_eq!(command.current_dir, Some(PathBuf::from("/games/doom")));
+
+        let bare = build_command(&exe("doom")).unwrap();
+        assert_eq!(bare.current_dir, None);
+    }
+
+    #[test]
+    fn configured_working_dir_and_env_apply() {
+        let mut entry = exe("/games/doom/doom.x86_64");
+        entry.working_dir = "/games/doom/data".into();
+        entry.env.insert("DXVK_HUD".into(), "fps".into());
+
+        let command = build_command(&entry).unwrap();
+        assert_eq!(command.current_dir, Some(PathBuf::from("/games/doom/data")));
+        assert_eq!(command.env.get("DXVK_HUD").map(String::as_str), Some("fps"));
+    }
+
+    #[test]
+    fn paths_and_args_are_expanded() {
+        let home = expand::home_dir().unwrap();
+        let mut entry = exe("~/games/doom/doom.x86_64");
+        entry.working_dir = "~/games/doom/$$data".into();
+        entry.launch_args = r#"--config "~/doom cfg" --price=$$5"#.into();
+
+        let command = build_command(&entry).unwrap();
+        assert_eq!(command.program, format!("{home}/games/doom/doom.x86_64"));
+        assert_eq!(
+            command.current_dir,
+            Some(PathBuf::from(format!("{home}/games/doom/$data")))
+        );
+        assert_eq!(
+            command.args,
+            [
+                "--config".into(),
+                format!("{home}/doom cfg"),
+                "--price=$5".into()
+            ]
+        );
+
+        entry.launch_data = "$KSCOPE_UNSET_VARIABLE/doom".into();
+        assert_eq!(
+            build_command(&entry).unwrap_err(),
+            LaunchError::UndefinedVariable {
+                name: "KSCOPE_UNSET_VARIABLE".into()
+            }
+        );
+    }
+
+    #[test]
+    fn env_values_are_expanded() {
+        let home = expand::home_dir().unwrap();
+        let mut entry = exe("/games/hl/hl.exe");
+        entry.env.insert("SAVES".into(), "~/saves/$$1".into());
+        entry.runners = Some(vec![Runner {
+            command: "wine".into(),
+            env: BTreeMap::from([("WINEPREFIX".into(), "~/.wine".into())]),
+        }]);
+
+        let command = build_command(&entry).unwrap();
+        assert_eq!(command.env["SAVES"], format!("{home}/saves/$1"));
+        assert_eq!(command.env["WINEPREFIX"], format!("{home}/.wine"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn pre_launch_hooks_can_provide_the_target() {
+        let dir = std::env::temp_dir().join(format!("kscope-target-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let target = dir.join("game.sh");
+        let mut entry = exe(target.to_str().unwrap());
+        let command = build_command(&entry).unwrap();
+        assert_eq!(
+            before_spawn(&entry, &command).unwrap_err(),
+            LaunchError::NotFound {
+                path: target.display().to_string()
+            }
+        );
+
+        entry.hooks.pre_launch.push(hooks::Hook {
+            command: format!(
+                "touch {}",
+                args::join_posix(&[target.display().to_string()])
+            ),
+            timeout_secs: 5,
+        });
+        before_spawn(&entry, &command).unwrap();
+        assert!(target.exists());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
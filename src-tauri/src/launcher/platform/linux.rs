@@ -0,0 +1,26 @@
+use super::{Backend, LaunchCommand};
+
+/// Linux and other freedesktop systems.
+pub struct Linux;
+
+impl Backend for Linux {
+    fn steam(&self, url: &str) -> LaunchCommand {
+        LaunchCommand::new("steam").arg(url)
+    }
+
+    fn steam_silent(&self) -> LaunchCommand {
+        LaunchCommand::new("steam").arg("-silent")
+    }
+
+    fn exe(&self, path: &str, args: &[String]) -> LaunchCommand {
+        LaunchCommand::new(path).args(args.iter().cloned())
+    }
+
+    fn url(&self, url: &str) -> LaunchCommand {
+        LaunchCommand::new("xdg-open").arg(url)
+    }
+
+    fn script(&self, path: &str) -> LaunchCommand {
+        LaunchCommand::new("sh").arg(path)
+    }
+}
@@ -0,0 +1,27 @@
+use super::{Backend, LaunchCommand};
+
+/// macOS, where `open` resolves URLs and URL schemes.
+pub struct MacOs;
+
+impl Backend for MacOs {
+    fn steam(&self, url: &str) -> LaunchCommand {
+        LaunchCommand::new("open").arg(url)
+    }
+
+    /// `-g` keeps Steam in the background.
+    fn steam_silent(&self) -> LaunchCommand {
+        LaunchCommand::new("open").args(["-g", "-a", "Steam", "--args", "-silent"])
+    }
+
+    fn exe(&self, path: &str, args: &[String]) -> LaunchCommand {
+        LaunchCommand::new(path).args(args.iter().cloned())
+    }
+
+    fn url(&self, url: &str) -> LaunchCommand {
+        LaunchCommand::new("open").arg(url)
+    }
+
+    fn script(&self, path: &str) -> LaunchCommand {
+        LaunchCommand::new("sh").arg(path)
+    }
+}
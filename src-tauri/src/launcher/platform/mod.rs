@@ -0,0 +1,197 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::process::Command;
+
+use serde::Serialize;
+
+use super::{steam, LaunchType};
+
+#[cfg(any(target_os = "linux", test))]
+mod linux;
+#[cfg(any(target_os = "macos", test))]
+mod macos;
+#[cfg(any(windows, test))]
+mod windows;
+
+#[cfg(target_os = "linux")]
+pub use linux::Linux as Current;
+#[cfg(target_os = "macos")]
+pub use macos::MacOs as Current;
+#[cfg(windows)]
+pub use windows::Windows as Current;
+
+/// A program, its argv and the process context it starts in, built without
+/// touching the OS so it can be inspected (and asserted on) before anything
+/// is spawned.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LaunchCommand {
+    pub program: String,
+    pub args: Vec<String>,
+    pub current_dir: Option<PathBuf>,
+    /// Set on top of the inherited environment.
+    pub env: BTreeMap<String, String>,
+}
+
+impl LaunchCommand {
+    pub fn new(program: impl Into<String>) -> Self {
+        Self {
+            program: program.into(),
+            args: Vec::new(),
+            current_dir: None,
+            env: BTreeMap::new(),
+        }
+    }
+
+    pub fn arg(mut self, arg: impl Into<String>) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    pub fn args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.args.extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    /// On Unix the process leads a new process group, so stopping an entry
+    /// can signal the whole tree it starts.
+    pub fn to_command(&self) -> Command {
+        let mut command = Command::new(&self.program);
+        command.args(&self.args).envs(&self.env);
+        if let Some(dir) = &self.current_dir {
+            command.current_dir(dir);
+        }
+        #[cfg(unix)]
+        std::os::unix::process::CommandExt::process_group(&mut command, 0);
+        command
+    }
+}
+
+/// How one operating system starts each kind of entry.
+pub trait Backend {
+    /// Hand a `steam://` URL to the Steam client.
+    fn steam(&self, url: &str) -> LaunchCommand;
+
+    /// Start the Steam client minimized to the tray.
+    fn steam_silent(&self) -> LaunchCommand;
+
+    /// Start an executable directly.
+    fn exe(&self, path: &str, args: &[String]) -> LaunchCommand;
+
+    /// Open a URL with the desktop's default handler.
+    fn url(&self, url: &str) -> LaunchCommand;
+
+    /// Run a script (`.bat` / `.cmd` on Windows, `.sh` elsewhere).
+    fn script(&self, path: &str) -> LaunchCommand;
+}
+
+/// Map a launch type onto the given backend. `data` is the already validated
+/// `launch_data` value and `args` the parsed `launch_args`.
+pub fn command_for(
+    backend: &impl Backend,
+    launch_type: LaunchType,
+    data: &str,
+    args: &[String],
+) -> LaunchCommand {
+    match launch_type {
+        LaunchType::Steam => backend.steam(&steam::run_url(data, args)),
+        LaunchType::Exe => backend.exe(data, args),
+        LaunchType::Url => backend.url(data),
+        LaunchType::Bat => backend.script(data),
+        LaunchType::Macro => unreachable!("macros run their steps; build_command rejects them"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build(backend: &impl Backend, launch_type: LaunchType, data: &str) -> LaunchCommand {
+        let args = vec!["--fullscreen".to_string(), "mod path".to_string()];
+        command_for(backend, launch_type, data, &args)
+    }
+
+    fn argv(command: &LaunchCommand) -> Vec<&str> {
+        std::iter::once(command.program.as_str())
+            .chain(command.args.iter().map(String::as_str))
+            .collect()
+    }
+
+    #[test]
+    fn linux_commands() {
+        let b = linux::Linux;
+        assert_eq!(
+            argv(&build(&b, LaunchType::Steam, "1245620")),
+            [
+                "steam",
+                "steam://run/1245620//--fullscreen%20%22mod%20path%22/"
+            ]
+        );
+        assert_eq!(
+            argv(&command_for(&b, LaunchType::Steam, "1245620", &[])),
+            ["steam", "steam://rungameid/1245620"]
+        );
+        assert_eq!(argv(&b.steam_silent()), ["steam", "-silent"]);
+        assert_eq!(
+            argv(&build(&b, LaunchType::Exe, "/opt/game/run")),
+            ["/opt/game/run", "--fullscreen", "mod path"]
+        );
+        assert_eq!(
+            argv(&build(&b, LaunchType::Url, "https://example.com")),
+            ["xdg-open", "https://example.com"]
+        );
+        assert_eq!(
+            argv(&build(&b, LaunchType::Bat, "/home/me/start.sh")),
+            ["sh", "/home/me/start.sh"]
+        );
+    }
+
+    #[test]
+    fn windows_commands() {
+        let b = windows::Windows;
+        assert_eq!(
+            argv(&command_for(&b, LaunchType::Steam, "1245620", &[])),
+            ["explorer", "steam://rungameid/1245620"]
+        );
+        assert_eq!(
+            argv(&build(&b, LaunchType::Exe, "C:/Games/Game.exe")),
+            ["C:/Games/Game.exe", "--fullscreen", "mod path"]
+        );
+        assert_eq!(
+            argv(&build(&b, LaunchType::Url, "https://example.com")),
+            ["explorer", "https://example.com"]
+        );
+        assert_eq!(
+            argv(&build(&b, LaunchType::Bat, "C:/Games/launch.bat")),
+            ["C:/Games/launch.bat"]
+        );
+    }
+
+    #[test]
+    fn macos_commands() {
+        let b = macos::MacOs;
+        assert_eq!(
+            argv(&command_for(&b, LaunchType::Steam, "1245620", &[])),
+            ["open", "steam://rungameid/1245620"]
+        );
+        assert_eq!(
+            argv(&b.steam_silent()),
+            ["open", "-g", "-a", "Steam", "--args", "-silent"]
+        );
+        assert_eq!(
+            argv(&build(&b, LaunchType::Exe, "/Applications/Game")),
+            ["/Applications/Game", "--fullscreen", "mod path"]
+        );
+        assert_eq!(
+            argv(&build(&b, LaunchType::Url, "https://example.com")),
+            ["open", "https://example.com"]
+        );
+        assert_eq!(
+            argv(&build(&b, LaunchType::Bat, "/Users/me/start.sh")),
+            ["sh", "/Users/me/start.sh"]
+        );
+    }
+}
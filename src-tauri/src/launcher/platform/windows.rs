@@ -0,0 +1,36 @@
+use super::super::steam;
+use super::{Backend, LaunchCommand};
+
+/// Windows. Nothing here goes through `cmd /c start`: URLs are handed to
+/// `explorer`, and std spawns `.bat` / `.cmd` files through the shell itself.
+pub struct Windows;
+
+impl Backend for Windows {
+    fn steam(&self, url: &str) -> LaunchCommand {
+        LaunchCommand::new("explorer").arg(url)
+    }
+
+    /// `steam.exe` is usually not on `PATH`, so look in the default
+    /// install folders first.
+    fn steam_silent(&self) -> LaunchCommand {
+        let exe = steam::install_roots()
+            .into_iter()
+            .map(|root| root.join("steam.exe"))
+            .find(|exe| exe.is_file())
+            .map(|exe| exe.display().to_string())
+            .unwrap_or_else(|| "steam.exe".into());
+        LaunchCommand::new(exe).arg("-silent")
+    }
+
+    fn exe(&self, path: &str, args: &[String]) -> LaunchCommand {
+        LaunchCommand::new(path).args(args.iter().cloned())
+    }
+
+    fn url(&self, url: &str) -> LaunchCommand {
+        LaunchCommand::new("explorer").arg(url)
+    }
+
+    fn script(&self, path: &str) -> LaunchCommand {
+        LaunchCommand::new(path)
+    }
+}
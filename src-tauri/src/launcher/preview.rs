@@ -0,0 +1,257 @@
+use std::env;
+
+use rusqlite::Connection;
+use serde::Serialize;
+use tauri::State;
+
+use super::hooks::{self, Hook};
+use super::platform::{self, Backend};
+use super::{load_entry, prepare, LaunchError, LaunchType, PreparedLaunch};
+use crate::db::Database;
+
+/// Everything `launch_entry` would run for an entry, without running it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LaunchPreview {
+    pub entry_id: i64,
+    /// The profile that was applied, if any.
+    pub profile: Option<String>,
+    pub program: String,
+    pub args: Vec<String>,
+    pub current_dir: Option<String>,
+    /// Variables set on top of K-Scope's own environment.
+    pub env: Vec<EnvChange>,
+    pub pre_launch: Vec<HookPreview>,
+    /// Run after the pre-launch hooks if Steam is not running yet.
+    pub start_steam: Option<Vec<String>>,
+    pub post_exit: Vec<HookPreview>,
+    /// Tracked launches get a playtime session and run post-exit hooks.
+    pub tracked: bool,
+    pub single_instance: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EnvChange {
+    pub name: String,
+    pub value: String,
+    /// The inherited value being replaced; `None` if the variable is new.
+    pub previous: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HookPreview {
+    /// The hook as configured.
+    pub command: String,
+    pub timeout_secs: u64,
+    pub program: String,
+    pub args: Vec<String>,
+}
+
+/// Describe a prepared launch. `inherited` looks up a variable in the
+/// environment the process would inherit.
+pub fn describe(
+    prepared: &PreparedLaunch,
+    inherited: impl Fn(&str) -> Option<String>,
+) -> Result<LaunchPreview, LaunchError> {
+    let PreparedLaunch {
+        entry,
+        profile,
+        command,
+    } = prepared;
+    let describe_hooks = |hooks: &[Hook]| {
+        hooks
+            .iter()
+            .map(|hook| {
+                let built = hooks::hook_command(hook, command)?;
+                Ok(HookPreview {
+                    command: hook.command.clone(),
+                    timeout_secs: hook.timeout_secs,
+                    program: built.program,
+                    args: built.args,
+                })
+            })
+            .collect::<Result<Vec<_>, hooks::HookError>>()
+    };
+
+    Ok(LaunchPreview {
+        entry_id: entry.id,
+        profile: profile.clone(),
+        program: command.program.clone(),
+        args: command.args.clone(),
+        current_dir: command
+            .current_dir
+            .as_ref()
+            .map(|dir| dir.display().to_string()),
+        env: command
+            .env
+            .iter()
+            .map(|(name, value)| EnvChange {
+                name: name.clone(),
+                value: value.clone(),
+                previous: inherited(name),
+            })
+            .collect(),
+        pre_launch: describe_hooks(&entry.hooks.pre_launch)?,
+        start_steam: (entry.launch_type == LaunchType::Steam && entry.start_steam_silently).then(
+            || {
+                let silent = platform::Current.steam_silent();
+                std::iter::once(silent.program).chain(silent.args).collect()
+            },
+        ),
+        post_exit: describe_hooks(&entry.hooks.post_exit)?,
+        tracked: matches!(entry.launch_type, LaunchType::Exe | LaunchType::Bat),
+        single_instance: entry.single_instance,
+    })
+}
+
+/// Prepare an entry for [`describe`]. Macros run their steps rather than
+/// a command of their own, so they are refused with `MacroHasNoCommand`.
+pub fn prepare_preview(
+    conn: &Connection,
+    id: i64,
+    profile: Option<&str>,
+) -> Result<PreparedLaunch, LaunchError> {
+    let entry = load_entry(conn, id)?;
+    if entry.launch_type == LaunchType::Macro {
+        return Err(LaunchError::MacroHasNoCommand { name: entry.name });
+    }
+    prepare(conn, id, profile)
+}
+
+// --- COMMANDS ---
+
+/// Show exactly what `launch_entry` would run for an entry and profile,
+/// built by the same code path, without spawning anything.
+#[tauri::command]
+pub fn preview_launch(
+    db: State<'_, Database>,
+    id: i64,
+    profile: Option<String>,
+) -> Result<LaunchPreview, LaunchError> {
+    let prepared = prepare_preview(&db.conn(), id, profile.as_deref())?;
+    describe(&prepared, |name| env::var(name).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::launcher::profiles;
+    use crate::settings;
+
+    fn conn() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            r#"CREATE TABLE entries (
+                   id INTEGER PRIMARY KEY, name TEXT, launch_type TEXT, launch_data TEXT,
+                   launch_args TEXT, working_dir TEXT, env TEXT, hooks TEXT,
+                   single_instance INTEGER, start_steam_silently INTEGER, runners TEXT
+               );
+               INSERT INTO entries VALUES (
+                   1, 'Doom', 'exe', '/games/doom/doom', '-skill 4 +map "E1 M1"', '',
+                   '{"WINEPREFIX":"/pfx","HOME":"/home/player"}',
+                   '{"pre_launch":[{"command":"mount-iso /isos/doom.iso","timeout_secs":10}],
+                     "post_exit":[{"command":"umount /mnt/doom"}]}',
+                   1, 0, NULL
+               );
+               INSERT INTO entries VALUES (
+                   2, 'Portal', 'steam', '400', '-novid', '', NULL, NULL, NULL, 1, NULL
+               );
+               INSERT INTO entries VALUES (
+                   3, 'Session', 'macro', '', '', '', NULL, NULL, 0, 0, NULL
+               );"#,
+        )
+        .unwrap();
+        conn.execute_batch(profiles::SCHEMA).unwrap();
+        conn.execute_batch(settings::SCHEMA).unwrap();
+        conn
+    }
+
+    fn preview(conn: &Connection, id: i64, profile: Option<&str>) -> serde_json::Value {
+        let prepared = prepare_preview(conn, id, profile).unwrap();
+        let inherited = |name: &str| (name == "HOME").then(|| "/home/me".to_string());
+        serde_json::to_value(describe(&prepared, inherited).unwrap()).unwrap()
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn snapshot_exe_with_hooks() {
+        assert_eq!(
+            preview(&conn(), 1, None),
+            json!({
+                "entry_id": 1,
+                "profile": null,
+                "program": "/games/doom/doom",
+                "args": ["-skill", "4", "+map", "E1 M1"],
+                "current_dir": "/games/doom",
+                "env": [
+                    { "name": "HOME", "value": "/home/player", "previous": "/home/me" },
+                    { "name": "WINEPREFIX", "value": "/pfx", "previous": null }
+                ],
+                "pre_launch": [{
+                    "command": "mount-iso /isos/doom.iso",
+                    "timeout_secs": 10,
+                    "program": "mount-iso",
+                    "args": ["/isos/doom.iso"]
+                }],
+                "start_steam": null,
+                "post_exit": [{
+                    "command": "umount /mnt/doom",
+                    "timeout_secs": 30,
+                    "program": "umount",
+                    "args": ["/mnt/doom"]
+                }],
+                "tracked": true,
+                "single_instance": true
+            })
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn snapshot_default_profile_and_steam() {
+        let mut conn = conn();
+        profiles::save(
+            &mut conn,
+            &profiles::LaunchProfile {
+                id: None,
+                entry_id: 1,
+                name: "Nightmare".into(),
+                launch_args: "-skill 5".into(),
+                working_dir: "/games/doom/base".into(),
+                env: [("WINEPREFIX".to_string(), "/pfx-nm".to_string())].into(),
+                is_default: true,
+            },
+        )
+        .unwrap();
+
+        let nightmare = preview(&conn, 1, None);
+        assert_eq!(nightmare["profile"], "Nightmare");
+        assert_eq!(nightmare["args"], json!(["-skill", "5"]));
+        assert_eq!(nightmare["current_dir"], "/games/doom/base");
+        assert_eq!(nightmare["env"][1]["value"], "/pfx-nm");
+
+        let steam = preview(&conn, 2, None);
+        assert_eq!(steam["program"], "steam");
+        assert_eq!(steam["args"], json!(["steam://run/400//-novid/"]));
+        assert_eq!(steam["start_steam"], json!(["steam", "-silent"]));
+        assert_eq!(steam["current_dir"], json!(null));
+        assert_eq!(steam["tracked"], false);
+    }
+
+    #[test]
+    fn unknown_profile_is_an_error() {
+        assert!(matches!(
+            prepare(&conn(), 1, Some("Missing")),
+            Err(LaunchError::ProfileNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn macros_have_no_command_to_preview() {
+        assert!(matches!(
+            prepare_preview(&conn(), 3, None),
+            Err(LaunchError::MacroHasNoCommand { name }) if name == "Session"
+        ));
+    }
+}
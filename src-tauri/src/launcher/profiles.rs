@@ -0,0 +1,341 @@
+use std::collections::BTreeMap;
+
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use super::{LaunchEntry, LaunchError};
+use crate::backups::BackupStore;
+use crate::db::{json_column, Database};
+use crate::entries;
+
+/// Named launch variants of an entry. At most one per entry is the default.
+pub const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS launch_profiles (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        entry_id INTEGER NOT NULL REFERENCES entries(id) ON DELETE CASCADE,
+        name TEXT NOT NULL,
+        launch_args TEXT NOT NULL DEFAULT '',
+        working_dir TEXT NOT NULL DEFAULT '',
+        env TEXT NOT NULL DEFAULT '{}',
+        is_default INTEGER NOT NULL DEFAULT 0,
+        UNIQUE (entry_id, name)
+    );
+";
+
+/// A launch variant. Its args replace the entry's, a non-empty working
+/// directory replaces the entry's, and its env is layered over the entry's.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LaunchProfile {
+    /// `None` when saving a new profile.
+    pub id: Option<i64>,
+    pub entry_id: i64,
+    pub name: String,
+    #[serde(default)]
+    pub launch_args: String,
+    #[serde(default)]
+    pub working_dir: String,
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    #[serde(default)]
+    pub is_default: bool,
+}
+
+impl LaunchProfile {
+    fn from_row(row: &Row<'_>) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get("id")?,
+            entry_id: row.get("entry_id")?,
+            name: row.get("name")?,
+            launch_args: row.get("launch_args")?,
+            working_dir: row.get("working_dir")?,
+            env: json_column(row, "env")?,
+            is_default: row.get("is_default")?,
+        })
+    }
+
+    /// Layer this profile over an entry's own settings.
+    pub fn apply(&self, entry: &mut LaunchEntry) {
+        entry.launch_args = self.launch_args.clone();
+        if !self.working_dir.trim().is_empty() {
+            entry.working_dir = self.working_dir.clone();
+        }
+        entry
+            .env
+            .extend(self.env.iter().map(|(k, v)| (k.clone(), v.clone())));
+    }
+}
+
+pub fn list(conn: &Connection, entry_id: i64) -> rusqlite::Result<Vec<LaunchProfile>> {
+    let mut stmt = conn.prepare(
+        "SELECT * FROM launch_profiles WHERE entry_id = ?1 ORDER BY is_default DESC, name",
+    )?;
+    let rows = stmt.query_map(params![entry_id], LaunchProfile::from_row)?;
+    rows.collect()
+}
+
+/// The profile to launch with: the named one, or the entry's default when
+/// `name` is `None`. `Ok(None)` means "use the entry as stored".
+pub fn resolve(
+    conn: &Connection,
+    entry_id: i64,
+    name: Option<&str>,
+) -> Result<Option<LaunchProfile>, LaunchError> {
+    let found = match name {
+        Some(name) => conn.query_row(
+            "SELECT * FROM launch_profiles WHERE entry_id = ?1 AND name = ?2",
+            params![entry_id, name],
+            LaunchProfile::from_row,
+        ),
+        None => conn.query_row(
+            "SELECT * FROM launch_profiles WHERE entry_id = ?1 AND is_default = 1",
+            params![entry_id],
+            LaunchProfile::from_row,
+        ),
+    }
+    .optional()?;
+
+    match (name, found) {
+        (Some(name), None) => Err(LaunchError::ProfileNotFound { name: name.into() }),
+        (_, found) => Ok(found),
+    }
+}
+
+/// Insert or update a profile and return its id. Making a profile the
+/// default clears the flag on the entry's other profiles.
+pub fn save(conn: &mut Connection, profile: &LaunchProfile) -> Result<i64, String> {
+    let name = profile.name.trim();
+    if name.is_empty() {
+        return Err("Profile name cannot be empty".into());
+    }
+    super::args::split(&profile.launch_args).map_err(|e| e.to_string())?;
+    entries::check_env(&profile.env)?;
+    let env = serde_json::to_string(&profile.env).map_err(|e| e.to_string())?;
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    if profile.is_default {
+        tx.execute(
+            "UPDATE launch_profiles SET is_default = 0 WHERE entry_id = ?1",
+            params![profile.entry_id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    let id = match profile.id {
+        Some(id) => {
+            let changed = tx
+                .execute(
+                    "UPDATE launch_profiles
+                     SET name = ?2, launch_args = ?3, working_dir = ?4, env = ?5, is_default = ?6
+                     WHERE id = ?1 AND entry_id = ?7",
+                    params![
+                        id,
+                        name,
+                        profile.launch_args,
+                        profile.working_dir,
+                        env,
+                        profile.is_default,
+                        profile.entry_id
+                    ],
+                )
+                .map_err(|e| e.to_string())?;
+            if changed == 0 {
+                return Err(format!("Launch profile {id} not found"));
+            }
+            id
+        }
+        None => {
+            tx.execute(
+                "INSERT INTO launch_profiles
+                 (entry_id, name, launch_args, working_dir, env, is_default)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    profile.entry_id,
+                    name,
+                    profile.launch_args,
+                    profile.working_dir,
+                    env,
+                    profile.is_default
+                ],
+            )
+            .map_err(|e| e.to_string())?;
+            tx.last_insert_rowid()
+        }
+    };
+
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(id)
+}
+
+/// Make `profile_id` the entry's default, or clear the default with `None`.
+/// A profile of another entry is rejected and leaves the default alone.
+pub fn set_default(
+    conn: &Connection,
+    entry_id: i64,
+    profile_id: Option<i64>,
+) -> Result<(), String> {
+    if let Some(id) = profile_id {
+        let owner: Option<i64> = conn
+            .query_row(
+                "SELECT entry_id FROM launch_profiles WHERE id = ?1",
+                params![id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| e.to_string())?;
+        match owner {
+            None => return Err(format!("Launch profile {id} not found")),
+            Some(owner) if owner != entry_id => {
+                return Err(format!("Launch profile {id} belongs to another entry"))
+            }
+            Some(_) => {}
+        }
+    }
+    conn.execute(
+        "UPDATE launch_profiles SET is_default = (id IS ?2) WHERE entry_id = ?1",
+        params![entry_id, profile_id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// --- COMMANDS ---
+
+#[tauri::command]
+pub fn list_launch_profiles(
+    db: State<'_, Database>,
+    entry_id: i64,
+) -> Result<Vec<LaunchProfile>, String> {
+    list(&db.conn(), entry_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn save_launch_profile(db: State<'_, Database>, profile: LaunchProfile) -> Result<i64, String> {
+    save(&mut db.conn(), &profile)
+}
+
+#[tauri::command]
+pub fn delete_launch_profile(
+    db: State<'_, Database>,
+    backups: State<'_, BackupStore>,
+    id: i64,
+) -> Result<(), String> {
+    let conn = db.conn();
+    backups.snapshot(&conn, "delete-profile")?;
+    conn.execute("DELETE FROM launch_profiles WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn set_default_launch_profile(
+    db: State<'_, Database>,
+    entry_id: i64,
+    profile_id: Option<i64>,
+) -> Result<(), String> {
+    set_default(&db.conn(), entry_id, profile_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::launcher::hooks::Hooks;
+    use crate::launcher::LaunchType;
+
+    fn conn() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE entries (id INTEGER PRIMARY KEY); INSERT INTO entries VALUES (1);",
+        )
+        .unwrap();
+        conn.execute_batch(SCHEMA).unwrap();
+        conn
+    }
+
+    fn profile(name: &str, args: &str, is_default: bool) -> LaunchProfile {
+        LaunchProfile {
+            id: None,
+            entry_id: 1,
+            name: name.into(),
+            launch_args: args.into(),
+            working_dir: String::new(),
+            env: BTreeMap::new(),
+            is_default,
+        }
+    }
+
+    #[test]
+    fn default_profile_is_exclusive() {
+        let mut conn = conn();
+        let dx11 = save(&mut conn, &profile("DX11", "--dx11", true)).unwrap();
+        let vulkan = save(&mut conn, &profile("Vulkan", "--vulkan", true)).unwrap();
+
+        assert_eq!(resolve(&conn, 1, None).unwrap().unwrap().id, Some(vulkan));
+
+        set_default(&conn, 1, Some(dx11)).unwrap();
+        assert_eq!(resolve(&conn, 1, None).unwrap().unwrap().id, Some(dx11));
+
+        set_default(&conn, 1, None).unwrap();
+        assert_eq!(resolve(&conn, 1, None).unwrap(), None);
+
+        // A profile of another entry must not clear this entry's default.
+        set_default(&conn, 1, Some(dx11)).unwrap();
+        conn.execute("INSERT INTO entries VALUES (2)", []).unwrap();
+        let mut other = profile("Other", "", false);
+        other.entry_id = 2;
+        let other = save(&mut conn, &other).unwrap();
+        assert!(set_default(&conn, 1, Some(other)).is_err());
+        assert!(set_default(&conn, 1, Some(999)).is_err());
+        assert_eq!(resolve(&conn, 1, None).unwrap().unwrap().id, Some(dx11));
+    }
+
+    #[test]
+    fn resolve_by_name() {
+        let mut conn = conn();
+        save(&mut conn, &profile("Vulkan", "--vulkan", false)).unwrap();
+
+        let found = resolve(&conn, 1, Some("Vulkan")).unwrap().unwrap();
+        assert_eq!(found.launch_args, "--vulkan");
+        assert!(resolve(&conn, 1, Some("Missing")).is_err());
+    }
+
+    #[test]
+    fn save_rejects_bad_input() {
+        let mut conn = conn();
+        assert!(save(&mut conn, &profile("  ", "", false)).is_err());
+        assert!(save(&mut conn, &profile("Broken", "\"open", false)).is_err());
+        let mut bad_env = profile("Env", "", false);
+        bad_env.env.insert("A=B".into(), "x".into());
+        assert_eq!(
+            save(&mut conn, &bad_env).unwrap_err(),
+            "Invalid environment variable name: \"A=B\""
+        );
+        assert!(list(&conn, 1).unwrap().is_empty());
+    }
+
+    #[test]
+    fn apply_layers_over_entry() {
+        let mut entry = LaunchEntry {
+            id: 1,
+            name: "Game".into(),
+            launch_type: LaunchType::Exe,
+            launch_data: "/games/game".into(),
+            launch_args: "--windowed".into(),
+            working_dir: "/games".into(),
+            env: BTreeMap::from([("A".into(), "entry".into()), ("B".into(), "entry".into())]),
+            hooks: Hooks::default(),
+            single_instance: false,
+            start_steam_silently: false,
+            runners: None,
+        };
+        let mut vulkan = profile("Vulkan", "--vulkan", false);
+        vulkan.env.insert("B".into(), "profile".into());
+
+        vulkan.apply(&mut entry);
+
+        assert_eq!(entry.launch_args, "--vulkan");
+        assert_eq!(entry.working_dir, "/games");
+        assert_eq!(entry.env["A"], "entry");
+        assert_eq!(entry.env["B"], "profile");
+    }
+}
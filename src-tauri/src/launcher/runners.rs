@@ -0,0 +1,228 @@
+use std::collections::BTreeMap;
+
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use super::args;
+use super::platform::LaunchCommand;
+use super::LaunchError;
+use crate::db::Database;
+use crate::entries;
+use crate::settings;
+
+/// Settings key of the chain used by entries without their own.
+const GLOBAL_KEY: &str = "runners";
+
+/// A compatibility layer or wrapper that the target is started through,
+/// e.g. `wine`, `proton run`, `gamemoderun` or `mangohud`. The command line
+/// is split with the launch argument tokenizer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Runner {
+    pub command: String,
+    /// Set for the whole launch, e.g. `WINEPREFIX`. Values are expanded
+    /// like the entry's own env.
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+}
+
+/// Wrap a command in a runner chain, outermost runner first:
+/// `[gamemoderun, mangohud, wine]` around `game.exe -x` gives
+/// `gamemoderun mangohud wine game.exe -x`.
+///
+/// Runner env is applied under the command's own env, so a variable set on
+/// the entry wins over the same variable set by a runner.
+pub fn wrap(command: LaunchCommand, chain: &[Runner]) -> Result<LaunchCommand, LaunchError> {
+    let mut argv = Vec::new();
+    let mut env = BTreeMap::new();
+    for runner in chain {
+        let invalid = |reason: String| LaunchError::InvalidRunner {
+            command: runner.command.clone(),
+            reason,
+        };
+        let words = args::split(&runner.command).map_err(|e| invalid(e.to_string()))?;
+        if words.is_empty() {
+            return Err(invalid("empty command".into()));
+        }
+        argv.extend(words);
+        env.extend(runner.env.clone());
+    }
+    if argv.is_empty() {
+        return Ok(command);
+    }
+
+    argv.push(command.program);
+    argv.extend(command.args);
+    env.extend(command.env);
+
+    let mut words = argv.into_iter();
+    let mut wrapped = LaunchCommand::new(words.next().unwrap_or_default()).args(words);
+    wrapped.current_dir = command.current_dir;
+    wrapped.env = env;
+    Ok(wrapped)
+}
+
+/// The chain for entries that do not set their own.
+pub fn global(conn: &Connection) -> rusqlite::Result<Vec<Runner>> {
+    Ok(settings::get(conn, GLOBAL_KEY)?.unwrap_or_default())
+}
+
+// --- COMMANDS ---
+
+#[tauri::command]
+pub fn global_runners(db: State<'_, Database>) -> Result<Vec<Runner>, String> {
+    global(&db.conn()).map_err(|e| e.to_string())
+}
+
+/// Replace the global chain. Every runner must tokenize to a command and
+/// set only valid env names.
+#[tauri::command]
+pub fn save_global_runners(
+    db: State<'_, Database>,
+    runners: Vec<Runner>,
+) -> Result<(), LaunchError> {
+    wrap(LaunchCommand::new("target"), &runners)?;
+    for runner in &runners {
+        entries::check_env(&runner.env).map_err(|reason| LaunchError::InvalidRunner {
+            command: runner.command.clone(),
+            reason,
+        })?;
+    }
+    settings::set(&db.conn(), GLOBAL_KEY, &runners)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn runner(command: &str, env: &[(&str, &str)]) -> Runner {
+        Runner {
+            command: command.into(),
+            env: env
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        }
+    }
+
+    fn game() -> LaunchCommand {
+        let mut command = LaunchCommand::new("/games/hl/hl.exe").args(["-novid", "+map c1a0"]);
+        command.current_dir = Some("/games/hl".into());
+        command
+    }
+
+    fn argv(command: &LaunchCommand) -> Vec<&str> {
+        std::iter::once(command.program.as_str())
+            .chain(command.args.iter().map(String::as_str))
+            .collect()
+    }
+
+    #[test]
+    fn composed_argv() {
+        let wine = runner("wine", &[("WINEPREFIX", "/pfx")]);
+        let proton = runner(
+            r#""/steam/Proton 9.0/proton" run"#,
+            &[("STEAM_COMPAT_DATA_PATH", "/compat")],
+        );
+        let gamemode = runner("gamemoderun", &[]);
+        let mangohud = runner("mangohud --dlsym", &[]);
+
+        let cases: &[(&[Runner], &[&str])] = &[
+            (&[], &["/games/hl/hl.exe", "-novid", "+map c1a0"]),
+            (
+                std::slice::from_ref(&wine),
+                &["wine", "/games/hl/hl.exe", "-novid", "+map c1a0"],
+            ),
+            (
+                std::slice::from_ref(&proton),
+                &[
+                    "/steam/Proton 9.0/proton",
+                    "run",
+                    "/games/hl/hl.exe",
+                    "-novid",
+                    "+map c1a0",
+                ],
+            ),
+            (
+                &[gamemode.clone(), mangohud.clone(), wine.clone()],
+                &[
+                    "gamemoderun",
+                    "mangohud",
+                    "--dlsym",
+                    "wine",
+                    "/games/hl/hl.exe",
+                    "-novid",
+                    "+map c1a0",
+                ],
+            ),
+            (
+                &[gamemode, proton.clone()],
+                &[
+                    "gamemoderun",
+                    "/steam/Proton 9.0/proton",
+                    "run",
+                    "/games/hl/hl.exe",
+                    "-novid",
+                    "+map c1a0",
+                ],
+            ),
+            (
+                &[mangohud],
+                &[
+                    "mangohud",
+                    "--dlsym",
+                    "/games/hl/hl.exe",
+                    "-novid",
+                    "+map c1a0",
+                ],
+            ),
+        ];
+
+        for (chain, expected) in cases {
+            let wrapped = wrap(game(), chain).unwrap();
+            assert_eq!(argv(&wrapped), *expected, "chain: {chain:?}");
+            assert_eq!(wrapped.current_dir, game().current_dir);
+        }
+    }
+
+    #[test]
+    fn entry_env_wins_over_runner_env() {
+        let mut command = game();
+        command.env.insert("WINEPREFIX".into(), "/entry-pfx".into());
+        let chain = [runner(
+            "wine",
+            &[("WINEPREFIX", "/pfx"), ("WINEDEBUG", "-all")],
+        )];
+
+        let wrapped = wrap(command, &chain).unwrap();
+        assert_eq!(wrapped.env["WINEPREFIX"], "/entry-pfx");
+        assert_eq!(wrapped.env["WINEDEBUG"], "-all");
+    }
+
+    #[test]
+    fn empty_and_malformed_runners_are_rejected() {
+        assert!(matches!(
+            wrap(game(), &[runner("  ", &[])]),
+            Err(LaunchError::InvalidRunner { .. })
+        ));
+        assert!(matches!(
+            wrap(game(), &[runner("wine \"open", &[])]),
+            Err(LaunchError::InvalidRunner { .. })
+        ));
+    }
+
+    #[test]
+    fn global_chain_round_trips() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(settings::SCHEMA).unwrap();
+        assert_eq!(global(&conn).unwrap(), []);
+
+        let chain = vec![
+            runner("gamemoderun", &[]),
+            runner("wine", &[("WINEPREFIX", "/pfx")]),
+        ];
+        settings::set(&conn, GLOBAL_KEY, &chain).unwrap();
+        assert_eq!(global(&conn).unwrap(), chain);
+    }
+}
@@ -0,0 +1,190 @@
+use std::env;
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::platform::{self, Backend};
+use super::{spawn, tracker, LaunchError};
+
+/// How long to wait for a silently started Steam client to show up.
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(30);
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// The URL that starts an app. Launch options use the
+/// `steam://run/<appid>//<args>/` form; without any the plain
+/// `steam://rungameid/<appid>` is used.
+pub fn run_url(app_id: &str, args: &[String]) -> String {
+    if args.is_empty() {
+        return format!("steam://rungameid/{app_id}");
+    }
+    let options = args
+        .iter()
+        .map(|arg| quote(arg))
+        .collect::<Vec<_>>()
+        .join(" ");
+    format!("steam://run/{app_id}//{}/", percent_encode(&options))
+}
+
+/// The app id in a `steam://rungameid/<appid>` or `steam://run/<appid>`
+/// URL, as written by Steam's own shortcuts.
+pub fn app_id_from_url(url: &str) -> Option<&str> {
+    let rest = url
+        .strip_prefix("steam://rungameid/")
+        .or_else(|| url.strip_prefix("steam://run/"))?;
+    let id = rest.split('/').next()?;
+    (!id.is_empty() && id.bytes().all(|b| b.is_ascii_digit())).then_some(id)
+}
+
+/// Steam splits launch options on spaces and honours double quotes.
+fn quote(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains(|c: char| c.is_whitespace() || c == '"') {
+        return arg.to_string();
+    }
+    format!("\"{}\"", arg.replace('"', "\\\""))
+}
+
+/// Percent-encode everything but RFC 3986 unreserved characters, so
+/// slashes and spaces in options cannot end the URL path segment.
+fn percent_encode(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for byte in s.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                out.push(byte as char)
+            }
+            _ => out.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    out
+}
+
+/// Default Steam installation folders for the current platform.
+pub fn install_roots() -> Vec<PathBuf> {
+    let mut roots = Vec::new();
+    if cfg!(windows) {
+        for var in ["ProgramFiles(x86)", "ProgramFiles"] {
+            if let Some(dir) = env::var_os(var) {
+                roots.push(PathBuf::from(dir).join("Steam"));
+            }
+        }
+    } else if let Some(home) = env::var_os("HOME").map(PathBuf::from) {
+        if cfg!(target_os = "macos") {
+            roots.push(home.join("Library/Application Support/Steam"));
+        } else {
+            roots.push(home.join(".steam/steam"));
+            roots.push(home.join(".local/share/Steam"));
+            roots.push(home.join(".var/app/com.valvesoftware.Steam/.local/share/Steam"));
+        }
+    }
+    roots
+}
+
+/// Start the Steam client minimized to the tray unless it is already
+/// running, and wait until its process shows up so the launch URL reaches
+/// a live client.
+pub fn ensure_running() -> Result<(), LaunchError> {
+    if os::is_running() {
+        return Ok(());
+    }
+
+    let command = platform::Current.steam_silent();
+    let child = spawn(&command).map_err(|e| LaunchError::spawn(&command.program, e))?;
+    tracker::reap(child);
+
+    let deadline = Instant::now() + STARTUP_TIMEOUT;
+    while !os::is_running() {
+        if Instant::now() >= deadline {
+            return Err(LaunchError::SteamNotStarted {
+                secs: STARTUP_TIMEOUT.as_secs(),
+            });
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+mod os {
+    use std::fs;
+
+    /// Look for a process named `steam` in `/proc`.
+    pub fn is_running() -> bool {
+        let Ok(procs) = fs::read_dir("/proc") else {
+            return false;
+        };
+        procs.flatten().any(|proc| {
+            fs::read_to_string(proc.path().join("comm")).is_ok_and(|comm| comm.trim() == "steam")
+        })
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod os {
+    use std::process::{Command, Stdio};
+
+    pub fn is_running() -> bool {
+        Command::new("pgrep")
+            .args(["-x", "steam_osx"])
+            .stdout(Stdio::null())
+            .status()
+            .is_ok_and(|status| status.success())
+    }
+}
+
+#[cfg(windows)]
+mod os {
+    use std::os::windows::process::CommandExt;
+    use std::process::Command;
+
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    pub fn is_running() -> bool {
+        Command::new("tasklist")
+            .args(["/FI", "IMAGENAME eq steam.exe", "/NH"])
+            .creation_flags(CREATE_NO_WINDOW)
+            .output()
+            .is_ok_and(|out| {
+                String::from_utf8_lossy(&out.stdout)
+                    .to_ascii_lowercase()
+                    .contains("steam.exe")
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn run_urls() {
+        assert_eq!(run_url("220", &[]), "steam://rungameid/220");
+        assert_eq!(
+            run_url("220", &args(&["-novid", "+map", "c1a0"])),
+            "steam://run/220//-novid%20%2Bmap%20c1a0/"
+        );
+        assert_eq!(
+            run_url("220", &args(&["-game", "my mod/dir"])),
+            "steam://run/220//-game%20%22my%20mod%2Fdir%22/"
+        );
+        assert_eq!(
+            run_url("220", &args(&["-say", "\"hi\""])),
+            "steam://run/220//-say%20%22%5C%22hi%5C%22%22/"
+        );
+    }
+
+    #[test]
+    fn app_ids_from_urls() {
+        assert_eq!(app_id_from_url("steam://rungameid/620"), Some("620"));
+        assert_eq!(app_id_from_url("steam://run/220//-novid/"), Some("220"));
+        assert_eq!(app_id_from_url("steam://rungameid/"), None);
+        assert_eq!(app_id_from_url("steam://open/games"), None);
+        assert_eq!(
+            app_id_from_url("https://store.steampowered.com/app/620"),
+            None
+        );
+    }
+}
@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+use std::process::Child;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::Instant;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+use crate::db::unix_now;
+
+/// Event emitted when a tracked process exits.
+pub const ENTRY_EXITED: &str = "entry-exited";
+
+/// A launched process that is still alive.
+#[derive(Debug, Clone, Serialize)]
+pub struct RunningEntry {
+    pub entry_id: i64,
+    pub pid: u32,
+    /// Unix timestamp (seconds) of the spawn.
+    pub started_at: i64,
+}
+
+/// Payload of the `entry-exited` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct EntryExited {
+    pub entry_id: i64,
+    pub pid: u32,
+    /// `None` when the process was ended by a signal.
+    pub exit_code: Option<i32>,
+    pub duration_secs: u64,
+}
+
+/// Children spawned by the launcher, keyed by entry id. Each child gets a
+/// waiter thread that removes it again and emits `entry-exited`.
+#[derive(Default)]
+pub struct ProcessRegistry {
+    running: Arc<Mutex<HashMap<i64, Vec<RunningEntry>>>>,
+    launch_locks: Mutex<HashMap<i64, Arc<Mutex<()>>>>,
+}
+
+impl ProcessRegistry {
+    /// Register a freshly spawned child and start waiting on it. `on_exit`
+    /// runs on the waiter thread once the child is gone, before the event
+    /// is emitted.
+    pub fn track<F>(&self, app: AppHandle, entry_id: i64, child: Child, on_exit: F) -> RunningEntry
+    where
+        F: FnOnce(&EntryExited) + Send + 'static,
+    {
+        self.watch(entry_id, child, move |exited| {
+            on_exit(&exited);
+            let _ = app.emit(ENTRY_EXITED, exited);
+        })
+    }
+
+    /// `track` without the event, for callers that have no app handle.
+    pub(super) fn watch<F>(&self, entry_id: i64, mut child: Child, on_exit: F) -> RunningEntry
+    where
+        F: FnOnce(EntryExited) + Send + 'static,
+    {
+        let entry = RunningEntry {
+            entry_id,
+            pid: child.id(),
+            started_at: unix_now(),
+        };
+
+        lock(&self.running)
+            .entry(entry_id)
+            .or_default()
+            .push(entry.clone());
+
+        let running = Arc::clone(&self.running);
+        let pid = entry.pid;
+        let started = Instant::now();
+
+        thread::spawn(move || {
+            let exit_code = child.wait().ok().and_then(|status| status.code());
+
+            {
+                let mut running = lock(&running);
+                if let Some(list) = running.get_mut(&entry_id) {
+                    list.retain(|p| p.pid != pid);
+                    if list.is_empty() {
+                        running.remove(&entry_id);
+                    }
+                }
+            }
+
+            let exited = EntryExited {
+                entry_id,
+                pid,
+                exit_code,
+                duration_secs: started.elapsed().as_secs(),
+            };
+            on_exit(exited);
+        });
+
+        entry
+    }
+
+    /// Serializes launches of one entry. Holding it from the "is it running?"
+    /// check until the child is tracked means a double Enter cannot slip a
+    /// second copy past the single-instance check.
+    pub fn launch_lock(&self, entry_id: i64) -> Arc<Mutex<()>> {
+        Arc::clone(lock(&self.launch_locks).entry(entry_id).or_default())
+    }
+
+    /// The oldest live process of an entry, if any.
+    pub fn find(&self, entry_id: i64) -> Option<RunningEntry> {
+        lock(&self.running)
+            .get(&entry_id)
+            .and_then(|list| list.first().cloned())
+    }
+
+    /// Pids of every live process of an entry.
+    pub fn pids(&self, entry_id: i64) -> Vec<u32> {
+        lock(&self.running)
+            .get(&entry_id)
+            .map(|list| list.iter().map(|p| p.pid).collect())
+            .unwrap_or_default()
+    }
+
+    /// Everything that is currently alive, oldest first.
+    pub fn running(&self) -> Vec<RunningEntry> {
+        let mut all: Vec<_> = lock(&self.running).values().flatten().cloned().collect();
+        all.sort_by_key(|p| (p.started_at, p.pid));
+        all
+    }
+}
+
+/// Wait on a child we do not track (e.g. the short-lived `xdg-open` helper),
+/// so it does not linger as a zombie.
+pub fn reap(mut child: Child) {
+    thread::spawn(move || {
+        let _ = child.wait();
+    });
+}
+
+pub(crate) fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
@@ -1,5 +1,19 @@
 use tauri::Manager;
 
+mod backups;
+mod categories;
+mod db;
+mod entries;
+mod health;
+mod image_pipeline;
+mod images;
+mod launcher;
+mod library;
+mod migrations;
+mod playtime;
+mod settings;
+mod shortcuts;
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
@@ -7,15 +21,76 @@ pub fn run() {
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
-        .plugin(tauri_plugin_sql::Builder::new().build())
-        
+        .plugin(
+            tauri_plugin_log::Builder::new()
+                .level(log::LevelFilter::Info)
+                .build(),
+        )
         .setup(|app| {
             let window = app.get_webview_window("main").unwrap();
             window
                 .set_background_color(Some(tauri::window::Color(0, 0, 0, 0)))
                 .ok();
+
+            let images =
+                images::ImageStore::new(app.path().app_data_dir()?.join(images::IMAGE_DIR));
+            let db_dir = app.path().app_config_dir()?;
+            let database = db::Database::open(&db_dir, &images)?;
+            playtime::close_interrupted_sessions(&database.conn())?;
+            let backups = backups::BackupStore::new(db_dir.join(backups::BACKUP_DIR));
+            if let Err(e) = backups.snapshot(&database.conn(), backups::STARTUP) {
+                log::error!("startup backup failed: {e}");
+            }
+            app.manage(database);
+            app.manage(images);
+            app.manage(backups);
+            app.manage(launcher::tracker::ProcessRegistry::default());
+            playtime::spawn_heartbeat(app.handle().clone());
             Ok(())
         })
+        .invoke_handler(tauri::generate_handler![
+            backups::list_backups,
+            backups::restore_backup,
+            backups::backup_settings,
+            backups::save_backup_settings,
+            categories::list_categories,
+            categories::create_category,
+            categories::update_category,
+            categories::delete_category,
+            categories::reorder_categories,
+            entries::list_entries,
+            entries::get_entry,
+            entries::create_entry,
+            entries::update_entry,
+            entries::delete_entry,
+            launcher::launch_entry,
+            launcher::parse_launch_args,
+            launcher::expand::literal_path,
+            launcher::expand::portable_path,
+            launcher::running_entries,
+            launcher::preview::preview_launch,
+            launcher::control::stop_entry,
+            launcher::control::kill_entry,
+            launcher::profiles::list_launch_profiles,
+            launcher::profiles::save_launch_profile,
+            launcher::profiles::delete_launch_profile,
+            launcher::profiles::set_default_launch_profile,
+            launcher::macros::get_macro,
+            launcher::runners::global_runners,
+            launcher::runners::save_global_runners,
+            health::scan_library_health,
+            health::library_health,
+            images::image_dir,
+            image_pipeline::store_image,
+            image_pipeline::thumbnails,
+            image_pipeline::thumbnail_settings,
+            image_pipeline::save_thumbnail_settings,
+            library::export_library,
+            library::import_library,
+            playtime::entry_playtime,
+            playtime::library_playtime,
+            shortcuts::import_shortcut,
+        ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
@@ -0,0 +1,1140 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::Path;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use uuid::Uuid;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::backups::BackupStore;
+use crate::categories::{self, CategoryFields, DisplayMode, EntrySort};
+use crate::db::{unix_now, Database};
+use crate::entries::{self, EntryFields};
+use crate::images::{self, ImageStore};
+use crate::launcher::macros::{self, FailurePolicy, Macro, MacroMode, MacroStep};
+use crate::launcher::{args, profiles, LaunchType};
+
+/// Version of the document layout. Bump it when a change would make older
+/// builds misread a file; they refuse anything newer than they know.
+///
+/// Version 1 had a `type` of `game` or `app` per entry instead of
+/// categories; it is read into the default categories. Version 3 added
+/// macro entries, which version 2 builds cannot read.
+pub const VERSION: u32 = 3;
+
+/// Name of the document inside a zip export.
+const ZIP_DOCUMENT: &str = "library.json";
+/// Folder of the images inside a zip export, one file per hash.
+const ZIP_IMAGES: &str = "images/";
+
+/// A whole library in a form that can move between machines. Entries are
+/// matched by UUID on import, since row ids differ from one database to
+/// the next.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Library {
+    pub version: u32,
+    pub exported_at: i64,
+    /// In tab order.
+    #[serde(default)]
+    pub categories: Vec<CategoryFields>,
+    pub entries: Vec<LibraryEntry>,
+    /// Base64 image bytes by hash. Empty in zip exports, which carry the
+    /// files themselves under `images/`.
+    #[serde(default)]
+    pub images: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LibraryEntry {
+    pub uuid: String,
+    /// Name of the category, matched without regard to case on import.
+    #[serde(default)]
+    pub category: String,
+    /// Version 1 only.
+    #[serde(rename = "type", default, skip_serializing)]
+    pub legacy_type: Option<String>,
+    #[serde(flatten)]
+    pub fields: EntryFields,
+    #[serde(default)]
+    pub deprecated: bool,
+    #[serde(default)]
+    pub created_at: i64,
+    #[serde(default)]
+    pub profiles: Vec<LibraryProfile>,
+    /// What a `macro` entry runs.
+    #[serde(rename = "macro", default, skip_serializing_if = "Option::is_none")]
+    pub macro_definition: Option<LibraryMacro>,
+}
+
+/// A launch profile without the row ids that tie it to one database.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LibraryProfile {
+    pub name: String,
+    #[serde(default)]
+    pub launch_args: String,
+    #[serde(default)]
+    pub working_dir: String,
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    #[serde(default)]
+    pub is_default: bool,
+}
+
+/// A macro whose steps name their entries by UUID.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LibraryMacro {
+    pub mode: MacroMode,
+    pub on_failure: FailurePolicy,
+    pub steps: Vec<LibraryMacroStep>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LibraryMacroStep {
+    pub entry: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    #[serde(default)]
+    pub delay_ms: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    /// One JSON file with the images inline.
+    Json,
+    /// One TOML file with the images inline.
+    Toml,
+    /// `library.json` plus the image files, which stay small since they
+    /// are not base64-encoded.
+    Zip,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportMode {
+    /// Add new entries and update matching ones; keep everything else.
+    Merge,
+    /// Make the library match the file: entries missing from it are
+    /// removed, with their playtime and profiles.
+    Replace,
+}
+
+/// What an import changed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ImportReport {
+    pub added: usize,
+    pub updated: usize,
+    pub removed: usize,
+}
+
+// --- EXPORT ---
+
+/// Read the whole library. With `inline_images`, the art goes into the
+/// document; otherwise the caller stores it next to it.
+pub fn collect(
+    conn: &Connection,
+    store: &ImageStore,
+    inline_images: bool,
+) -> Result<Library, String> {
+    let categories = categories::list(conn).map_err(|e| e.to_string())?;
+    let mut library = Library {
+        version: VERSION,
+        exported_at: unix_now(),
+        categories: categories.iter().map(|c| c.fields.clone()).collect(),
+        entries: Vec::new(),
+        images: BTreeMap::new(),
+    };
+    // Oldest first, so an import creates the rows in their original order.
+    let mut list = entries::list(conn, None).map_err(|e| e.to_string())?;
+    list.sort_by_key(|e| (e.created_at, e.id));
+    for entry in list {
+        // Entries without art, e.g. ones whose image could not be migrated,
+        // are exported without it.
+        let hash = &entry.fields.image_hash;
+        if !hash.is_empty() && !store.contains(hash) {
+            return Err(format!(
+                "The image of {} is missing from the image store",
+                entry.fields.name
+            ));
+        }
+        if inline_images && !hash.is_empty() && !library.images.contains_key(hash) {
+            let bytes = store.get(hash).map_err(|e| e.to_string())?;
+            library.images.insert(hash.clone(), STANDARD.encode(bytes));
+        }
+        let profiles = profiles::list(conn, entry.id)
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .map(|p| LibraryProfile {
+                name: p.name,
+                launch_args: p.launch_args,
+                working_dir: p.working_dir,
+                env: p.env,
+                is_default: p.is_default,
+            })
+            .collect();
+        let category = categories
+            .iter()
+            .find(|c| c.id == entry.category_id)
+            .map(|c| c.fields.name.clone())
+            .unwrap_or_default();
+        let macro_definition = match macros::get(conn, entry.id).map_err(|e| e.to_string())? {
+            Some(definition) => Some(export_macro(conn, definition)?),
+            None => None,
+        };
+        library.entries.push(LibraryEntry {
+            uuid: entry.uuid,
+            category,
+            legacy_type: None,
+            fields: entry.fields,
+            deprecated: entry.deprecated,
+            created_at: entry.created_at,
+            profiles,
+            macro_definition,
+        });
+    }
+    Ok(library)
+}
+
+fn export_macro(conn: &Connection, definition: Macro) -> Result<LibraryMacro, String> {
+    let steps = definition
+        .steps
+        .into_iter()
+        .map(|step| {
+            let entry = conn
+                .query_row(
+                    "SELECT uuid FROM entries WHERE id = ?1",
+                    params![step.entry_id],
+                    |row| row.get(0),
+                )
+                .map_err(|e| e.to_string())?;
+            Ok(LibraryMacroStep {
+                entry,
+                profile: step.profile,
+                delay_ms: step.delay_ms,
+            })
+        })
+        .collect::<Result<_, String>>()?;
+    Ok(LibraryMacro {
+        mode: definition.mode,
+        on_failure: definition.on_failure,
+        steps,
+    })
+}
+
+/// Write the library to `path` in `format`.
+pub fn export(
+    conn: &Connection,
+    store: &ImageStore,
+    path: &Path,
+    format: ExportFormat,
+) -> Result<(), String> {
+    let write_error = |e: std::io::Error| format!("Could not write {}: {e}", path.display());
+    let library = collect(conn, store, format != ExportFormat::Zip)?;
+    match format {
+        ExportFormat::Json => {
+            let text = serde_json::to_string_pretty(&library).map_err(|e| e.to_string())?;
+            fs::write(path, text).map_err(write_error)
+        }
+        ExportFormat::Toml => {
+            let text = toml::to_string(&library).map_err(|e| e.to_string())?;
+            fs::write(path, text).map_err(write_error)
+        }
+        ExportFormat::Zip => {
+            let zip_error = |e: zip::result::ZipError| e.to_string();
+            let mut zip = ZipWriter::new(File::create(path).map_err(write_error)?);
+            zip.start_file(ZIP_DOCUMENT, SimpleFileOptions::default())
+                .map_err(zip_error)?;
+            let text = serde_json::to_vec_pretty(&library).map_err(|e| e.to_string())?;
+            zip.write_all(&text).map_err(write_error)?;
+
+            // Images are compressed already.
+            let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+            let hashes: BTreeSet<_> = library
+                .entries
+                .iter()
+                .map(|e| &e.fields.image_hash)
+                .filter(|hash| !hash.is_empty())
+                .collect();
+            for hash in hashes {
+                let bytes = store.get(hash).map_err(|e| e.to_string())?;
+                zip.start_file(format!("{ZIP_IMAGES}{hash}"), stored)
+                    .map_err(zip_error)?;
+                zip.write_all(&bytes).map_err(write_error)?;
+            }
+            zip.finish().map_err(zip_error)?;
+            Ok(())
+        }
+    }
+}
+
+// --- IMPORT ---
+
+/// Read a library file, telling the format from its content. Images
+/// shipped in a zip are added to the store right away.
+pub fn read(path: &Path, store: &ImageStore) -> Result<Library, String> {
+    let read_error = |e: std::io::Error| format!("Could not read {}: {e}", path.display());
+    let bytes = fs::read(path).map_err(read_error)?;
+
+    if bytes.starts_with(b"PK\x03\x04") {
+        let zip_error = |e: zip::result::ZipError| format!("{}: {e}", path.display());
+        let mut zip = ZipArchive::new(std::io::Cursor::new(bytes)).map_err(zip_error)?;
+        let mut text = String::new();
+        zip.by_name(ZIP_DOCUMENT)
+            .map_err(zip_error)?
+            .read_to_string(&mut text)
+            .map_err(read_error)?;
+        let library = parse_json(&text)?;
+        for i in 0..zip.len() {
+            let mut file = zip.by_index(i).map_err(zip_error)?;
+            let Some(hash) = file.name().strip_prefix(ZIP_IMAGES).map(str::to_string) else {
+                continue;
+            };
+            let mut image = Vec::new();
+            file.read_to_end(&mut image).map_err(read_error)?;
+            put_image(store, &hash, &image)?;
+        }
+        return Ok(library);
+    }
+
+    let text = String::from_utf8(bytes)
+        .map_err(|_| format!("{} is not a library export", path.display()))?;
+    if text.trim_start().starts_with('{') {
+        parse_json(&text)
+    } else {
+        check_version(toml::from_str(&text).map_err(|e| format!("Invalid library file: {e}"))?)
+    }
+}
+
+fn parse_json(text: &str) -> Result<Library, String> {
+    check_version(serde_json::from_str(text).map_err(|e| format!("Invalid library file: {e}"))?)
+}
+
+fn check_version(mut library: Library) -> Result<Library, String> {
+    if library.version > VERSION {
+        return Err(format!(
+            "The library was exported by a newer version of the app (format {}, this build knows {VERSION})",
+            library.version
+        ));
+    }
+    if library.version < 2 {
+        library.categories = categories::DEFAULTS
+            .iter()
+            .map(|&(_, name, display_mode)| CategoryFields {
+                name: name.into(),
+                display_mode,
+                entry_sort: EntrySort::default(),
+            })
+            .collect();
+        for entry in &mut library.entries {
+            let legacy = entry.legacy_type.take().unwrap_or_default();
+            let Some(&(_, name, _)) = categories::DEFAULTS.iter().find(|d| d.0 == legacy) else {
+                return Err(format!(
+                    "{} has an unknown type: {legacy}",
+                    entry.fields.name
+                ));
+            };
+            entry.category = name.into();
+        }
+        library.version = VERSION;
+    }
+    Ok(library)
+}
+
+/// Add image bytes that claim to have `hash`, refusing ones that do not.
+fn put_image(store: &ImageStore, hash: &str, bytes: &[u8]) -> Result<(), String> {
+    if images::hash(bytes) != hash {
+        return Err(format!("Image {hash} in the library file is damaged"));
+    }
+    store.put(bytes).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Apply a library to the database in one transaction: entries are matched
+/// by UUID, and nothing changes if any entry is invalid or misses its art.
+/// An entry without an image hash has no art and needs none.
+pub fn apply(
+    conn: &mut Connection,
+    store: &ImageStore,
+    library: &Library,
+    mode: ImportMode,
+) -> Result<ImportReport, String> {
+    let mut seen = BTreeSet::new();
+    for entry in &library.entries {
+        let name = &entry.fields.name;
+        if Uuid::parse_str(&entry.uuid).is_err() {
+            return Err(format!("{name} has an invalid UUID: {}", entry.uuid));
+        }
+        if !seen.insert(entry.uuid.as_str()) {
+            return Err(format!("{name} appears twice in the library file"));
+        }
+        if entry.category.trim().is_empty() {
+            return Err(format!("{name} has no category"));
+        }
+    }
+    for (hash, data) in &library.images {
+        let bytes = STANDARD
+            .decode(data)
+            .map_err(|_| format!("Image {hash} in the library file is damaged"))?;
+        put_image(store, hash, &bytes)?;
+    }
+    for entry in &library.entries {
+        let hash = &entry.fields.image_hash;
+        if !hash.is_empty() && !store.contains(hash) {
+            return Err(format!(
+                "The image of {} is missing from the library file",
+                entry.fields.name
+            ));
+        }
+    }
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let category_ids = resolve_categories(&tx, library, mode)?;
+    let mut report = ImportReport::default();
+    if mode == ImportMode::Replace {
+        let local: Vec<(i64, Option<String>)> = tx
+            .prepare("SELECT id, uuid FROM entries")
+            .and_then(|mut stmt| {
+                stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+                    .collect()
+            })
+            .map_err(|e| e.to_string())?;
+        for (id, uuid) in local {
+            if !uuid.is_some_and(|u| seen.contains(u.as_str())) {
+                entries::delete(&tx, id)?;
+                report.removed += 1;
+            }
+        }
+    }
+    for entry in &library.entries {
+        let existing: Option<i64> = tx
+            .query_row(
+                "SELECT id FROM entries WHERE uuid = ?1",
+                params![entry.uuid],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| e.to_string())?;
+        let invalid = |e: String| format!("{}: {e}", entry.fields.name);
+        let category_id = category_ids[&entry.category.trim().to_ascii_lowercase()];
+        let id = match existing {
+            Some(id) => {
+                entries::update(&tx, id, category_id, entry.fields.clone()).map_err(invalid)?;
+                report.updated += 1;
+                id
+            }
+            None => {
+                let created =
+                    entries::create(&tx, category_id, entry.fields.clone()).map_err(invalid)?;
+                report.added += 1;
+                created.id
+            }
+        };
+        tx.execute(
+            "UPDATE entries SET uuid = ?2, deprecated = ?3, created_at = ?4 WHERE id = ?1",
+            params![id, entry.uuid, entry.deprecated, entry.created_at],
+        )
+        .map_err(|e| e.to_string())?;
+        merge_profiles(&tx, id, &entry.profiles).map_err(invalid)?;
+    }
+    // Steps may point at entries listed after their macro.
+    for entry in &library.entries {
+        if let Some(definition) = &entry.macro_definition {
+            if entry.fields.launch_type != LaunchType::Macro {
+                return Err(format!(
+                    "{} has macro steps but is not a macro",
+                    entry.fields.name
+                ));
+            }
+            import_macro(&tx, &entry.uuid, definition)
+                .map_err(|e| format!("{}: {e}", entry.fields.name))?;
+        }
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(report)
+}
+
+/// Replace the steps of the macro entry `uuid`, resolving step UUIDs
+/// against the file's entries and the local ones.
+fn import_macro(tx: &Transaction<'_>, uuid: &str, definition: &LibraryMacro) -> Result<(), String> {
+    let id_of = |uuid: &str| {
+        tx.query_row(
+            "SELECT id FROM entries WHERE uuid = ?1",
+            params![uuid],
+            |row| row.get::<_, i64>(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Macro step {uuid} is neither in the file nor in the library"))
+    };
+    let steps = definition
+        .steps
+        .iter()
+        .map(|step| {
+            Ok(MacroStep {
+                entry_id: id_of(&step.entry)?,
+                profile: step.profile.clone(),
+                delay_ms: step.delay_ms,
+            })
+        })
+        .collect::<Result<_, String>>()?;
+    macros::save(
+        tx,
+        id_of(uuid)?,
+        &Macro {
+            mode: definition.mode,
+            on_failure: definition.on_failure,
+            steps,
+        },
+    )
+}
+
+/// Find or create every category the file uses, by name regardless of
+/// case. Merge
+/// leaves existing categories as they are; replace also takes the file's
+/// display modes and order, with local-only categories after the file's.
+fn resolve_categories(
+    tx: &Transaction<'_>,
+    library: &Library,
+    mode: ImportMode,
+) -> Result<BTreeMap<String, i64>, String> {
+    let named = library.categories.iter().cloned();
+    // Entries may name categories the file does not list; they show as grids.
+    let unlisted = library.entries.iter().map(|e| CategoryFields {
+        name: e.category.clone(),
+        display_mode: DisplayMode::Grid,
+        entry_sort: EntrySort::default(),
+    });
+    let mut ids = BTreeMap::new();
+    let mut order = Vec::new();
+    for fields in named.chain(unlisted) {
+        let key = fields.name.trim().to_ascii_lowercase();
+        if ids.contains_key(&key) {
+            continue;
+        }
+        let existing = categories::find(tx, &fields.name).map_err(|e| e.to_string())?;
+        let id = match existing {
+            Some(category) if mode == ImportMode::Replace => {
+                categories::update(tx, category.id, fields)?.id
+            }
+            Some(category) => category.id,
+            None => categories::create(tx, fields)?.id,
+        };
+        ids.insert(key, id);
+        order.push(id);
+    }
+    if mode == ImportMode::Replace {
+        for category in categories::list(tx).map_err(|e| e.to_string())? {
+            if !order.contains(&category.id) {
+                order.push(category.id);
+            }
+        }
+        categories::write_order(tx, &order).map_err(|e| e.to_string())?;
+    }
+    Ok(ids)
+}
+
+/// Add the file's profiles to an entry, replacing ones of the same name.
+/// A default in the file takes over from the entry's current one.
+fn merge_profiles(
+    tx: &Transaction<'_>,
+    entry_id: i64,
+    profiles: &[LibraryProfile],
+) -> Result<(), String> {
+    if profiles.iter().any(|p| p.is_default) {
+        tx.execute(
+            "UPDATE launch_profiles SET is_default = 0 WHERE entry_id = ?1",
+            params![entry_id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    for profile in profiles {
+        let name = profile.name.trim();
+        if name.is_empty() {
+            return Err("Profile name cannot be empty".into());
+        }
+        args::split(&profile.launch_args).map_err(|e| format!("Profile {name}: {e}"))?;
+        entries::check_env(&profile.env).map_err(|e| format!("Profile {name}: {e}"))?;
+        let env = serde_json::to_string(&profile.env).map_err(|e| e.to_string())?;
+        tx.execute(
+            "INSERT INTO launch_profiles (entry_id, name, launch_args, working_dir, env, is_default)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT (entry_id, name) DO UPDATE SET
+                launch_args = ?3, working_dir = ?4, env = ?5, is_default = ?6",
+            params![
+                entry_id,
+                name,
+                profile.launch_args,
+                profile.working_dir,
+                env,
+                profile.is_default
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+// --- COMMANDS ---
+
+#[tauri::command(async)]
+pub fn export_library(
+    db: State<'_, Database>,
+    images: State<'_, ImageStore>,
+    path: String,
+    format: ExportFormat,
+) -> Result<(), String> {
+    export(&db.conn(), &images, Path::new(&path), format)
+}
+
+/// Import a file written by `export_library`, in any of its formats.
+#[tauri::command(async)]
+pub fn import_library(
+    db: State<'_, Database>,
+    images: State<'_, ImageStore>,
+    backups: State<'_, BackupStore>,
+    path: String,
+    mode: ImportMode,
+) -> Result<ImportReport, String> {
+    let library = read(Path::new(&path), &images)?;
+    let mut conn = db.conn();
+    backups.snapshot(&conn, "import")?;
+    apply(&mut conn, &images, &library, mode)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::launcher::hooks::{Hook, Hooks};
+    use crate::launcher::runners::Runner;
+    use crate::migrations;
+
+    /// A database and image store in a fresh temporary folder, as on one
+    /// machine. Removed again on drop.
+    struct Machine {
+        dir: PathBuf,
+        conn: Connection,
+        images: ImageStore,
+    }
+
+    impl Machine {
+        fn new(name: &str) -> Self {
+            let dir =
+                std::env::temp_dir().join(format!("kscope-library-{}-{name}", std::process::id()));
+            fs::remove_dir_all(&dir).ok();
+            fs::create_dir_all(&dir).unwrap();
+            let images = ImageStore::new(dir.join("images"));
+            let mut conn = Connection::open_in_memory().unwrap();
+            migrations::run(&mut conn, &images).unwrap();
+            Self { dir, conn, images }
+        }
+
+        /// Add an entry to the named category, creating it as a grid if
+        /// needed.
+        fn add(&self, category: &str, name: &str, launch_data: &str) -> i64 {
+            let category = match categories::find(&self.conn, category).unwrap() {
+                Some(category) => category,
+                None => categories::create(
+                    &self.conn,
+                    CategoryFields {
+                        name: category.into(),
+                        display_mode: DisplayMode::Grid,
+                        entry_sort: EntrySort::default(),
+                    },
+                )
+                .unwrap(),
+            };
+            let image_hash = self.images.put(name.as_bytes()).unwrap();
+            let fields = EntryFields {
+                name: name.into(),
+                launch_type: LaunchType::Exe,
+                launch_data: launch_data.into(),
+                launch_args: String::new(),
+                working_dir: String::new(),
+                env: BTreeMap::new(),
+                hooks: Hooks::default(),
+                single_instance: false,
+                start_steam_silently: false,
+                runners: None,
+                image_hash,
+            };
+            entries::create(&self.conn, category.id, fields).unwrap().id
+        }
+
+        fn library(&self) -> Library {
+            let mut library = collect(&self.conn, &self.images, false).unwrap();
+            library.exported_at = 0;
+            library
+        }
+    }
+
+    impl Drop for Machine {
+        fn drop(&mut self) {
+            fs::remove_dir_all(&self.dir).ok();
+        }
+    }
+
+    /// Two entries with every kind of setting, one with profiles, and one
+    /// in a category of its own.
+    fn home() -> Machine {
+        let home = Machine::new("home");
+        let portal = home.add("Games", "Portal", "~/Games/portal.exe");
+        home.conn
+            .execute(
+                r#"UPDATE entries SET launch_args = '-novid +map "test chamber"',
+                   env = '{"DXVK_HUD":"fps"}',
+                   hooks = '{"pre_launch":[{"command":"mount-games","timeout_secs":5}]}',
+                   runners = '[{"command":"wine","env":{"WINEPREFIX":"~/.wine"}}]',
+                   single_instance = 1, deprecated = 1
+                   WHERE id = ?1"#,
+                params![portal],
+            )
+            .unwrap();
+        for (name, args, is_default) in [("Dev", "-dev", true), ("Safe", "-safe", false)] {
+            home.conn
+                .execute(
+                    "INSERT INTO launch_profiles (entry_id, name, launch_args, env, is_default)
+                     VALUES (?1, ?2, ?3, '{\"X\":\"1\"}', ?4)",
+                    params![portal, name, args, is_default],
+                )
+                .unwrap();
+        }
+        home.add("Work tools", "Notes", "/usr/bin/notes");
+        home.conn
+            .execute(
+                "UPDATE categories SET entry_sort = 'name' WHERE name = 'Work tools'",
+                [],
+            )
+            .unwrap();
+        home
+    }
+
+    fn round_trip(format: ExportFormat, extension: &str) {
+        let home = home();
+        let path = home.dir.join(format!("library.{extension}"));
+        export(&home.conn, &home.images, &path, format).unwrap();
+
+        let mut laptop = Machine::new(&format!("laptop-{extension}"));
+        let library = read(&path, &laptop.images).unwrap();
+        let report = apply(
+            &mut laptop.conn,
+            &laptop.images,
+            &library,
+            ImportMode::Merge,
+        )
+        .unwrap();
+        assert_eq!(
+            report,
+            ImportReport {
+                added: 2,
+                updated: 0,
+                removed: 0
+            }
+        );
+        assert_eq!(laptop.library(), home.library(), "{format:?}");
+        for entry in home.library().entries {
+            assert_eq!(
+                laptop.images.get(&entry.fields.image_hash).unwrap(),
+                entry.fields.name.as_bytes()
+            );
+        }
+    }
+
+    #[test]
+    fn json_round_trip() {
+        round_trip(ExportFormat::Json, "json");
+    }
+
+    #[test]
+    fn toml_round_trip() {
+        round_trip(ExportFormat::Toml, "toml");
+    }
+
+    #[test]
+    fn zip_round_trip() {
+        round_trip(ExportFormat::Zip, "zip");
+    }
+
+    #[test]
+    fn entries_without_art_travel_without_it() {
+        let home = home();
+        home.conn
+            .execute(
+                "UPDATE entries SET image_hash = NULL WHERE name = 'Notes'",
+                [],
+            )
+            .unwrap();
+        for (format, extension) in [(ExportFormat::Json, "json"), (ExportFormat::Zip, "zip")] {
+            let path = home.dir.join(format!("no-art.{extension}"));
+            export(&home.conn, &home.images, &path, format).unwrap();
+
+            let mut laptop = Machine::new(&format!("no-art-{extension}"));
+            let library = read(&path, &laptop.images).unwrap();
+            apply(
+                &mut laptop.conn,
+                &laptop.images,
+                &library,
+                ImportMode::Merge,
+            )
+            .unwrap();
+            let imported = laptop.library();
+            assert_eq!(imported, home.library(), "{format:?}");
+            assert_eq!(imported.entries[1].fields.image_hash, "");
+        }
+    }
+
+    #[test]
+    fn merge_updates_by_uuid_and_keeps_the_rest() {
+        let home = home();
+        let mut library = collect(&home.conn, &home.images, true).unwrap();
+
+        let mut laptop = Machine::new("merge");
+        apply(
+            &mut laptop.conn,
+            &laptop.images,
+            &library,
+            ImportMode::Merge,
+        )
+        .unwrap();
+        let local = laptop.add("Games", "Doom", "doom");
+        let portal_id: i64 = laptop
+            .conn
+            .query_row("SELECT id FROM entries WHERE name = 'Portal'", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        laptop
+            .conn
+            .execute(
+                "INSERT INTO sessions (entry_id, started_at, last_seen_at) VALUES (?1, 1, 1)",
+                params![portal_id],
+            )
+            .unwrap();
+
+        // Renamed at home, with a changed profile.
+        library.entries[0].fields.name = "Portal (GOTY)".into();
+        library.entries[0].profiles[1].launch_args = "-safe -windowed".into();
+        let report = apply(
+            &mut laptop.conn,
+            &laptop.images,
+            &library,
+            ImportMode::Merge,
+        )
+        .unwrap();
+        assert_eq!(
+            report,
+            ImportReport {
+                added: 0,
+                updated: 2,
+                removed: 0
+            }
+        );
+
+        let portal = entries::get(&laptop.conn, portal_id).unwrap().unwrap();
+        assert_eq!(portal.fields.name, "Portal (GOTY)");
+        let profiles = profiles::list(&laptop.conn, portal_id).unwrap();
+        assert_eq!(profiles.len(), 2);
+        assert_eq!(profiles[1].launch_args, "-safe -windowed");
+        assert!(entries::get(&laptop.conn, local).unwrap().is_some());
+        // Same row, so its playtime is kept.
+        let sessions: i64 = laptop
+            .conn
+            .query_row("SELECT COUNT(*) FROM sessions", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(sessions, 1);
+    }
+
+    #[test]
+    fn replace_removes_entries_missing_from_the_file() {
+        let home = home();
+        let library = collect(&home.conn, &home.images, true).unwrap();
+
+        let mut laptop = Machine::new("replace");
+        let local = laptop.add("Games", "Doom", "doom");
+        let report = apply(
+            &mut laptop.conn,
+            &laptop.images,
+            &library,
+            ImportMode::Replace,
+        )
+        .unwrap();
+        assert_eq!(
+            report,
+            ImportReport {
+                added: 2,
+                updated: 0,
+                removed: 1
+            }
+        );
+        assert!(entries::get(&laptop.conn, local).unwrap().is_none());
+        assert_eq!(laptop.library(), home.library());
+    }
+
+    #[test]
+    fn bad_files_change_nothing() {
+        let home = home();
+        let library = collect(&home.conn, &home.images, true).unwrap();
+        let mut laptop = Machine::new("bad");
+        let before = laptop.library();
+
+        let mut invalid = library.clone();
+        invalid.entries[1].fields.launch_data = String::new();
+        let error = apply(
+            &mut laptop.conn,
+            &laptop.images,
+            &invalid,
+            ImportMode::Replace,
+        )
+        .unwrap_err();
+        assert!(error.starts_with("Notes: "), "{error}");
+
+        let mut damaged = library.clone();
+        let hash = damaged.entries[0].fields.image_hash.clone();
+        damaged.images.insert(hash, STANDARD.encode(b"other bytes"));
+        assert!(apply(
+            &mut laptop.conn,
+            &laptop.images,
+            &damaged,
+            ImportMode::Merge
+        )
+        .unwrap_err()
+        .contains("damaged"));
+
+        let mut twice = library.clone();
+        twice.entries.push(twice.entries[0].clone());
+        assert!(
+            apply(&mut laptop.conn, &laptop.images, &twice, ImportMode::Merge)
+                .unwrap_err()
+                .contains("twice")
+        );
+
+        let mut newer = library;
+        newer.version = VERSION + 1;
+        let path = laptop.dir.join("newer.json");
+        fs::write(&path, serde_json::to_string(&newer).unwrap()).unwrap();
+        assert!(read(&path, &laptop.images)
+            .unwrap_err()
+            .contains("newer version"));
+
+        assert_eq!(laptop.library(), before);
+    }
+
+    #[test]
+    fn categories_match_by_name() {
+        let home = home();
+        let library = collect(&home.conn, &home.images, true).unwrap();
+        assert_eq!(library.entries[1].category, "Work tools");
+
+        // Same category under another case and mode, and listed first.
+        let mut laptop = Machine::new("categories");
+        laptop.add("work TOOLS", "Slack", "slack");
+        let ids: Vec<i64> = categories::list(&laptop.conn)
+            .unwrap()
+            .iter()
+            .map(|c| c.id)
+            .collect();
+        categories::reorder(&mut laptop.conn, &[ids[2], ids[0], ids[1]]).unwrap();
+
+        apply(
+            &mut laptop.conn,
+            &laptop.images,
+            &library,
+            ImportMode::Merge,
+        )
+        .unwrap();
+        let merged = laptop.library().categories;
+        assert_eq!(merged.len(), 3);
+        assert_eq!(
+            merged[0],
+            CategoryFields {
+                name: "work TOOLS".into(),
+                display_mode: DisplayMode::Grid,
+                entry_sort: EntrySort::default(),
+            }
+        );
+
+        apply(
+            &mut laptop.conn,
+            &laptop.images,
+            &library,
+            ImportMode::Replace,
+        )
+        .unwrap();
+        assert_eq!(laptop.library().categories, library.categories);
+    }
+
+    #[test]
+    fn reads_version_1_documents() {
+        let home = home();
+        let mut document =
+            serde_json::to_value(collect(&home.conn, &home.images, true).unwrap()).unwrap();
+        document["version"] = 1.into();
+        document.as_object_mut().unwrap().remove("categories");
+        for (entry, entry_type) in document["entries"]
+            .as_array_mut()
+            .unwrap()
+            .iter_mut()
+            .zip(["game", "app"])
+        {
+            let entry = entry.as_object_mut().unwrap();
+            entry.remove("category");
+            entry.insert("type".into(), entry_type.into());
+        }
+        let path = home.dir.join("v1.json");
+        fs::write(&path, document.to_string()).unwrap();
+
+        let mut laptop = Machine::new("v1");
+        let library = read(&path, &laptop.images).unwrap();
+        assert_eq!(library.version, VERSION);
+        let placed: Vec<_> = library
+            .entries
+            .iter()
+            .map(|e| e.category.as_str())
+            .collect();
+        assert_eq!(placed, ["Games", "Apps"]);
+        apply(
+            &mut laptop.conn,
+            &laptop.images,
+            &library,
+            ImportMode::Merge,
+        )
+        .unwrap();
+        assert_eq!(laptop.library().entries[1].category, "Apps");
+
+        document["entries"][0]["type"] = "tool".into();
+        fs::write(&path, document.to_string()).unwrap();
+        assert!(read(&path, &laptop.images)
+            .unwrap_err()
+            .contains("unknown type: tool"));
+    }
+
+    #[test]
+    fn documents_are_readable() {
+        let home = home();
+        let path = home.dir.join("library.toml");
+        export(&home.conn, &home.images, &path, ExportFormat::Toml).unwrap();
+        let text = fs::read_to_string(&path).unwrap();
+        assert!(
+            text.starts_with(&format!("version = {VERSION}\n")),
+            "{text}"
+        );
+        assert!(text.contains("name = \"Portal\""));
+
+        let entry = &home.library().entries[0];
+        assert_eq!(
+            entry.fields.hooks.pre_launch,
+            [Hook {
+                command: "mount-games".into(),
+                timeout_secs: 5
+            }]
+        );
+        assert_eq!(
+            entry.fields.runners.as_deref().map(|r| r[0].clone()),
+            Some(Runner {
+                command: "wine".into(),
+                env: BTreeMap::from([("WINEPREFIX".into(), "~/.wine".into())]),
+            })
+        );
+    }
+
+    #[test]
+    fn macros_travel_with_step_uuids() {
+        let home = home();
+        let session = home.add("Games", "Session", "session");
+        home.conn
+            .execute(
+                "UPDATE entries SET launch_type = 'macro', launch_data = '' WHERE id = ?1",
+                params![session],
+            )
+            .unwrap();
+        let ids: Vec<i64> = ["Notes", "Portal"]
+            .iter()
+            .map(|name| {
+                home.conn
+                    .query_row("SELECT id FROM entries WHERE name = ?1", [name], |row| {
+                        row.get(0)
+                    })
+                    .unwrap()
+            })
+            .collect();
+        let definition = Macro {
+            mode: MacroMode::Sequential,
+            on_failure: FailurePolicy::Continue,
+            steps: vec![
+                MacroStep {
+                    entry_id: ids[0],
+                    profile: None,
+                    delay_ms: 0,
+                },
+                MacroStep {
+                    entry_id: ids[1],
+                    profile: Some("Dev".into()),
+                    delay_ms: 3000,
+                },
+            ],
+        };
+        macros::save(&home.conn, session, &definition).unwrap();
+
+        let mut library = collect(&home.conn, &home.images, true).unwrap();
+        let steps = &library.entries[2].macro_definition.as_ref().unwrap().steps;
+        assert_eq!(steps[0].entry, library.entries[1].uuid);
+        assert_eq!(steps[1].entry, library.entries[0].uuid);
+        // The macro comes before the entries it runs.
+        library.entries.reverse();
+
+        let mut laptop = Machine::new("macros");
+        apply(
+            &mut laptop.conn,
+            &laptop.images,
+            &library,
+            ImportMode::Merge,
+        )
+        .unwrap();
+        let imported: i64 = laptop
+            .conn
+            .query_row("SELECT id FROM entries WHERE name = 'Session'", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        let steps = macros::get(&laptop.conn, imported).unwrap().unwrap().steps;
+        let names: Vec<String> = steps
+            .iter()
+            .map(|step| {
+                entries::get(&laptop.conn, step.entry_id)
+                    .unwrap()
+                    .unwrap()
+                    .fields
+                    .name
+            })
+            .collect();
+        assert_eq!(names, ["Notes", "Portal"]);
+        assert_eq!(steps[1].profile.as_deref(), Some("Dev"));
+        // Imported in another order, so compare by UUID.
+        let by_uuid = |machine: &Machine| {
+            let mut entries = machine.library().entries;
+            entries.sort_by(|a, b| a.uuid.cmp(&b.uuid));
+            entries
+        };
+        assert_eq!(by_uuid(&laptop), by_uuid(&home));
+
+        // A step that is nowhere to be found fails the whole import.
+        let mut dangling = library;
+        dangling.entries[0].macro_definition.as_mut().unwrap().steps[0].entry =
+            Uuid::new_v4().to_string();
+        let error = apply(
+            &mut laptop.conn,
+            &laptop.images,
+            &dangling,
+            ImportMode::Merge,
+        )
+        .unwrap_err();
+        assert!(error.starts_with("Session: Macro step"), "{error}");
+    }
+}
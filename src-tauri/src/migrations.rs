@@ -0,0 +1,731 @@
+use rusqlite::{params, Connection, Transaction};
+use uuid::Uuid;
+
+use crate::images::{self, ImageStore};
+use crate::launcher::{macros, profiles};
+use crate::{categories, health, playtime, settings};
+
+/// One schema change. Migration `n` (1-based position in [`MIGRATIONS`])
+/// moves the database from `user_version` `n - 1` to `n`. Migrations that
+/// move data out of the database get the image store.
+struct Migration {
+    description: &'static str,
+    up: fn(&Transaction<'_>, &ImageStore) -> rusqlite::Result<()>,
+}
+
+/// Every schema change, oldest first. Only ever append: a released
+/// migration must not change, since databases that ran it will not run it
+/// again.
+///
+/// Libraries created before versions were tracked sit at version 0 in any
+/// of the shapes the webview used to leave behind, so the early migrations
+/// look at what is there instead of assuming.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        description: "entries with a launch type (bat_path becomes launch_data)",
+        up: launch_types,
+    },
+    Migration {
+        description: "per-entry launch settings",
+        up: launch_settings,
+    },
+    Migration {
+        description: "tables owned by the launcher",
+        up: launcher_tables,
+    },
+    Migration {
+        description: "cover art in the image store",
+        up: image_store,
+    },
+    Migration {
+        description: "stable entry ids for export and import",
+        up: entry_uuids,
+    },
+    Migration {
+        description: "user-defined categories replace the game / app type",
+        up: categories,
+    },
+    Migration {
+        description: "macros become entries",
+        up: macro_entries,
+    },
+    Migration {
+        description: "per-category entry sort",
+        up: entry_sort,
+    },
+];
+
+/// The version a fully migrated database has.
+pub fn latest_version() -> i64 {
+    MIGRATIONS.len() as i64
+}
+
+/// Bring the database up to [`latest_version`]. Each migration runs in its
+/// own transaction together with the version bump, so a failure leaves the
+/// database at the last version that applied cleanly.
+///
+/// Foreign keys are off while migrating, because rebuilding a table drops
+/// the original, which would cascade into every table referencing it.
+/// References are checked once all migrations have run.
+pub fn run(conn: &mut Connection, images: &ImageStore) -> Result<(), String> {
+    let current: i64 = conn
+        .pragma_query_value(None, "user_version", |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    if current > latest_version() {
+        return Err(format!(
+            "The library was written by a newer version of the app (schema {current}, this build knows {})",
+            latest_version()
+        ));
+    }
+
+    conn.pragma_update(None, "foreign_keys", false)
+        .map_err(|e| e.to_string())?;
+    let result = apply(conn, images, current);
+    conn.pragma_update(None, "foreign_keys", true)
+        .map_err(|e| e.to_string())?;
+    result
+}
+
+fn apply(conn: &mut Connection, images: &ImageStore, current: i64) -> Result<(), String> {
+    for (version, migration) in (1..).zip(MIGRATIONS).skip(current as usize) {
+        let failed = |e: rusqlite::Error| {
+            format!(
+                "Migration {version} ({}) failed: {e}",
+                migration.description
+            )
+        };
+        let tx = conn.transaction().map_err(failed)?;
+        (migration.up)(&tx, images).map_err(failed)?;
+        tx.pragma_update(None, "user_version", version)
+            .map_err(failed)?;
+        tx.commit().map_err(failed)?;
+    }
+
+    let mut stmt = conn
+        .prepare("PRAGMA foreign_key_check")
+        .map_err(|e| e.to_string())?;
+    if let Some(table) = stmt
+        .query_map([], |row| row.get::<_, String>(0))
+        .map_err(|e| e.to_string())?
+        .next()
+    {
+        return Err(format!(
+            "Library has broken references in {} after migrating",
+            table.map_err(|e| e.to_string())?
+        ));
+    }
+    Ok(())
+}
+
+fn columns(tx: &Transaction<'_>, table: &str) -> rusqlite::Result<Vec<String>> {
+    let mut stmt = tx.prepare(&format!("PRAGMA table_info({table})"))?;
+    let names = stmt.query_map([], |row| row.get("name"))?;
+    names.collect()
+}
+
+const ENTRIES: &str = "
+    CREATE TABLE entries (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        type TEXT NOT NULL CHECK(type IN ('game', 'app')),
+        name TEXT NOT NULL,
+        launch_type TEXT NOT NULL DEFAULT 'bat' CHECK(launch_type IN ('steam', 'exe', 'url', 'bat')),
+        launch_data TEXT NOT NULL,
+        launch_args TEXT DEFAULT '',
+        image_data TEXT NOT NULL,
+        deprecated INTEGER DEFAULT 0,
+        created_at INTEGER DEFAULT (unixepoch())
+    );
+";
+
+/// Create `entries`, or rebuild the original table, where every entry ran
+/// a script from `bat_path`, into the launch type layout. The new table is
+/// built next to the old one and renamed into place, so references from
+/// other tables keep pointing at `entries`.
+fn launch_types(tx: &Transaction<'_>, _: &ImageStore) -> rusqlite::Result<()> {
+    let existing = columns(tx, "entries")?;
+    if existing.is_empty() {
+        return tx.execute_batch(ENTRIES);
+    }
+    if !existing.iter().any(|c| c == "bat_path") || existing.iter().any(|c| c == "launch_type") {
+        return Ok(());
+    }
+    tx.execute_batch(&format!(
+        "{}
+         INSERT INTO entries_new (id, type, name, launch_type, launch_data, launch_args, image_data, deprecated, created_at)
+         SELECT id, type, name, 'bat', bat_path, '', image_data, deprecated, created_at
+         FROM entries;
+         DROP TABLE entries;
+         ALTER TABLE entries_new RENAME TO entries;",
+        ENTRIES.replace("CREATE TABLE entries", "CREATE TABLE entries_new")
+    ))
+}
+
+/// Columns read by the launcher. Older builds added some of them from the
+/// webview, so only the missing ones are added.
+fn launch_settings(tx: &Transaction<'_>, _: &ImageStore) -> rusqlite::Result<()> {
+    let existing = columns(tx, "entries")?;
+    for (name, definition) in [
+        ("working_dir", "TEXT DEFAULT ''"),
+        ("env", "TEXT DEFAULT '{}'"),
+        ("hooks", "TEXT DEFAULT '{}'"),
+        ("single_instance", "INTEGER DEFAULT 0"),
+        ("start_steam_silently", "INTEGER DEFAULT 0"),
+        ("runners", "TEXT DEFAULT NULL"),
+    ] {
+        if !existing.iter().any(|c| c == name) {
+            tx.execute_batch(&format!(
+                "ALTER TABLE entries ADD COLUMN {name} {definition}"
+            ))?;
+        }
+    }
+    Ok(())
+}
+
+/// The macro tables before macros became entries; see [`macro_entries`].
+const STANDALONE_MACROS: &str = "
+    CREATE TABLE IF NOT EXISTS macros (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        name TEXT NOT NULL,
+        mode TEXT NOT NULL DEFAULT 'sequential',
+        on_failure TEXT NOT NULL DEFAULT 'stop',
+        created_at INTEGER DEFAULT (unixepoch())
+    );
+    CREATE TABLE IF NOT EXISTS macro_steps (
+        macro_id INTEGER NOT NULL REFERENCES macros(id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        entry_id INTEGER NOT NULL REFERENCES entries(id) ON DELETE CASCADE,
+        profile TEXT,
+        delay_ms INTEGER NOT NULL DEFAULT 0,
+        PRIMARY KEY (macro_id, position)
+    );
+";
+
+fn launcher_tables(tx: &Transaction<'_>, _: &ImageStore) -> rusqlite::Result<()> {
+    for schema in [
+        settings::SCHEMA,
+        playtime::SCHEMA,
+        profiles::SCHEMA,
+        STANDALONE_MACROS,
+        health::SCHEMA,
+    ] {
+        tx.execute_batch(schema)?;
+    }
+    Ok(())
+}
+
+/// Move the base64 data URLs in `image_data` into the image store and
+/// keep only their hash, so listing entries no longer reads every image.
+/// Values that are not data URLs are dropped and the entry shows no art.
+///
+/// Files already written stay in the store if the migration rolls back;
+/// they are found again by hash on the next attempt.
+fn image_store(tx: &Transaction<'_>, images: &ImageStore) -> rusqlite::Result<()> {
+    tx.execute_batch("ALTER TABLE entries ADD COLUMN image_hash TEXT")?;
+    let art: Vec<(i64, String)> = tx
+        .prepare("SELECT id, image_data FROM entries WHERE image_data LIKE 'data:%'")?
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<_>>()?;
+    for (id, url) in art {
+        let Some(bytes) = images::decode_data_url(&url) else {
+            continue;
+        };
+        let hash = images
+            .put(&bytes)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+        tx.execute(
+            "UPDATE entries SET image_hash = ?1 WHERE id = ?2",
+            params![hash, id],
+        )?;
+    }
+    tx.execute_batch("ALTER TABLE entries DROP COLUMN image_data")
+}
+
+/// Give every entry a random UUID that survives export and import, unlike
+/// its row id.
+fn entry_uuids(tx: &Transaction<'_>, _: &ImageStore) -> rusqlite::Result<()> {
+    tx.execute_batch("ALTER TABLE entries ADD COLUMN uuid TEXT")?;
+    let ids: Vec<i64> = tx
+        .prepare("SELECT id FROM entries")?
+        .query_map([], |row| row.get(0))?
+        .collect::<rusqlite::Result<_>>()?;
+    for id in ids {
+        tx.execute(
+            "UPDATE entries SET uuid = ?1 WHERE id = ?2",
+            params![Uuid::new_v4().to_string(), id],
+        )?;
+    }
+    tx.execute_batch("CREATE UNIQUE INDEX entries_uuid ON entries (uuid)")
+}
+
+/// Create the default categories and move every entry into the one its
+/// `type` maps to. `type` is dropped together with its CHECK, so the set of
+/// tabs is no longer fixed by the schema.
+fn categories(tx: &Transaction<'_>, _: &ImageStore) -> rusqlite::Result<()> {
+    tx.execute_batch(categories::SCHEMA)?;
+    tx.execute_batch(
+        "ALTER TABLE entries ADD COLUMN category_id INTEGER REFERENCES categories(id)",
+    )?;
+    for (position, (entry_type, name, display_mode)) in categories::DEFAULTS.iter().enumerate() {
+        tx.execute(
+            "INSERT INTO categories (name, display_mode, sort_order) VALUES (?1, ?2, ?3)",
+            params![name, display_mode, position as i64],
+        )?;
+        tx.execute(
+            "UPDATE entries SET category_id = ?1 WHERE type = ?2",
+            params![tx.last_insert_rowid(), entry_type],
+        )?;
+    }
+    tx.execute_batch(
+        "ALTER TABLE entries DROP COLUMN type;
+         CREATE INDEX entries_category ON entries (category_id);",
+    )
+}
+
+/// Every column `entries` has by now, with `macro` allowed as a launch
+/// type. Rebuilding is the only way to change the CHECK.
+const MACRO_ENTRIES: &str = "
+    CREATE TABLE entries_new (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        uuid TEXT,
+        category_id INTEGER REFERENCES categories(id),
+        name TEXT NOT NULL,
+        launch_type TEXT NOT NULL DEFAULT 'bat' CHECK(launch_type IN ('steam', 'exe', 'url', 'bat', 'macro')),
+        launch_data TEXT NOT NULL,
+        launch_args TEXT DEFAULT '',
+        working_dir TEXT DEFAULT '',
+        env TEXT DEFAULT '{}',
+        hooks TEXT DEFAULT '{}',
+        single_instance INTEGER DEFAULT 0,
+        start_steam_silently INTEGER DEFAULT 0,
+        runners TEXT DEFAULT NULL,
+        image_hash TEXT,
+        deprecated INTEGER DEFAULT 0,
+        created_at INTEGER DEFAULT (unixepoch())
+    );
+";
+
+/// Turn every macro into an entry of the `macro` launch type in the first
+/// category, so macros show up in the tabs and launch like any entry. The
+/// steps keep their order and now belong to the macro's entry. Migrated
+/// macros have no art until one is picked.
+fn macro_entries(tx: &Transaction<'_>, _: &ImageStore) -> rusqlite::Result<()> {
+    const COLUMNS: &str = "id, uuid, category_id, name, launch_type, launch_data, launch_args,
+        working_dir, env, hooks, single_instance, start_steam_silently, runners, image_hash,
+        deprecated, created_at";
+    tx.execute_batch(&format!(
+        "{MACRO_ENTRIES}
+         INSERT INTO entries_new ({COLUMNS}) SELECT {COLUMNS} FROM entries;
+         DROP TABLE entries;
+         ALTER TABLE entries_new RENAME TO entries;
+         CREATE UNIQUE INDEX entries_uuid ON entries (uuid);
+         CREATE INDEX entries_category ON entries (category_id);
+         ALTER TABLE macro_steps RENAME TO standalone_macro_steps;
+         ALTER TABLE macros RENAME TO standalone_macros;
+         {}",
+        macros::SCHEMA
+    ))?;
+
+    let old: Vec<(i64, String, String, String, Option<i64>)> = tx
+        .prepare(
+            "SELECT id, name, mode, on_failure, created_at FROM standalone_macros ORDER BY id",
+        )?
+        .query_map([], |row| {
+            Ok((
+                row.get(0)?,
+                row.get(1)?,
+                row.get(2)?,
+                row.get(3)?,
+                row.get(4)?,
+            ))
+        })?
+        .collect::<rusqlite::Result<_>>()?;
+    for (id, name, mode, on_failure, created_at) in old {
+        tx.execute(
+            "INSERT INTO entries (uuid, category_id, name, launch_type, launch_data, created_at)
+             VALUES (?1, (SELECT id FROM categories ORDER BY sort_order, id LIMIT 1),
+                     ?2, 'macro', '', COALESCE(?3, unixepoch()))",
+            params![Uuid::new_v4().to_string(), name, created_at],
+        )?;
+        let entry_id = tx.last_insert_rowid();
+        tx.execute(
+            "INSERT INTO macros (entry_id, mode, on_failure) VALUES (?1, ?2, ?3)",
+            params![entry_id, mode, on_failure],
+        )?;
+        tx.execute(
+            "INSERT INTO macro_steps (macro_id, position, entry_id, profile, delay_ms)
+             SELECT ?1, position, entry_id, profile, delay_ms
+             FROM standalone_macro_steps WHERE macro_id = ?2",
+            params![entry_id, id],
+        )?;
+    }
+    tx.execute_batch("DROP TABLE standalone_macro_steps; DROP TABLE standalone_macros;")
+}
+
+/// Let each category pick the order of its entries. Existing ones keep
+/// showing the newest first.
+fn entry_sort(tx: &Transaction<'_>, _: &ImageStore) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "ALTER TABLE categories ADD COLUMN entry_sort TEXT NOT NULL DEFAULT 'recently_added'
+            CHECK(entry_sort IN ('name', 'recently_added', 'recently_played', 'playtime'))",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    /// A copy of a fixture database and an empty image store next to it,
+    /// both removed again on drop.
+    struct Fixture {
+        path: PathBuf,
+        images: ImageStore,
+    }
+
+    impl Fixture {
+        fn copy(name: &str) -> Self {
+            let source = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                .join("fixtures/db")
+                .join(name);
+            // Tests run in parallel and may copy the same fixture.
+            static COPIES: AtomicUsize = AtomicUsize::new(0);
+            let copy = COPIES.fetch_add(1, Ordering::Relaxed);
+            let path = std::env::temp_dir().join(format!(
+                "kscope-migrations-{}-{copy}-{name}",
+                std::process::id()
+            ));
+            fs::copy(source, &path).unwrap();
+            let images = ImageStore::new(path.with_extension("images"));
+            fs::remove_dir_all(images.dir()).ok();
+            Self { path, images }
+        }
+
+        fn open(&self) -> Connection {
+            Connection::open(&self.path).unwrap()
+        }
+    }
+
+    impl Drop for Fixture {
+        fn drop(&mut self) {
+            fs::remove_file(&self.path).ok();
+            fs::remove_dir_all(self.images.dir()).ok();
+        }
+    }
+
+    /// For databases without art; nothing is written to it.
+    fn no_images() -> ImageStore {
+        ImageStore::new(std::env::temp_dir().join("kscope-migrations-no-images"))
+    }
+
+    fn version(conn: &Connection) -> i64 {
+        conn.pragma_query_value(None, "user_version", |row| row.get(0))
+            .unwrap()
+    }
+
+    fn entry_columns(conn: &Connection) -> Vec<String> {
+        let mut stmt = conn.prepare("PRAGMA table_info(entries)").unwrap();
+        let names = stmt.query_map([], |row| row.get("name")).unwrap();
+        names.collect::<rusqlite::Result<_>>().unwrap()
+    }
+
+    #[test]
+    fn bat_path_library_upgrades() {
+        let fixture = Fixture::copy("bat_path.db");
+        let mut conn = fixture.open();
+        assert_eq!(version(&conn), 0);
+
+        run(&mut conn, &fixture.images).unwrap();
+        assert_eq!(version(&conn), latest_version());
+
+        let columns = entry_columns(&conn);
+        assert!(!columns.contains(&"bat_path".to_string()));
+        assert!(!columns.contains(&"type".to_string()));
+        // Games and apps landed in the matching default categories.
+        let placed: Vec<(i64, String)> = conn
+            .prepare(
+                "SELECT e.id, c.name FROM entries e JOIN categories c ON c.id = e.category_id
+                 ORDER BY e.id",
+            )
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(
+            placed,
+            [(1, "Games".into()), (2, "Games".into()), (5, "Apps".into())]
+        );
+        // Both keep showing the newest entries first.
+        let sorts: Vec<String> = conn
+            .prepare("SELECT entry_sort FROM categories ORDER BY id")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(sorts, ["recently_added", "recently_added"]);
+        assert!(conn
+            .execute("UPDATE categories SET entry_sort = 'random'", [])
+            .is_err());
+        assert!(!columns.contains(&"image_data".to_string()));
+        for column in [
+            "launch_type",
+            "launch_data",
+            "runners",
+            "start_steam_silently",
+        ] {
+            assert!(columns.contains(&column.to_string()), "missing {column}");
+        }
+
+        let rows: Vec<(i64, String, String, String, bool, i64)> = conn
+            .prepare(
+                "SELECT id, name, launch_type, launch_data, deprecated, created_at
+                 FROM entries ORDER BY id",
+            )
+            .unwrap()
+            .query_map([], |row| {
+                Ok((
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                    row.get(5)?,
+                ))
+            })
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(
+            rows,
+            [
+                (
+                    1,
+                    "Wuthering Waves".into(),
+                    "bat".into(),
+                    "/scripts/launch_wuwa.bat".into(),
+                    false,
+                    1700000000
+                ),
+                (
+                    2,
+                    "Old Game".into(),
+                    "bat".into(),
+                    "C:/Games/old.bat".into(),
+                    true,
+                    1700000100
+                ),
+                (
+                    5,
+                    "Wiztree".into(),
+                    "bat".into(),
+                    "C:/scripts/launch_wiztree.bat".into(),
+                    false,
+                    1700000200
+                ),
+            ]
+        );
+
+        // The launcher tables exist and reference the rebuilt table.
+        conn.execute(
+            "INSERT INTO sessions (entry_id, started_at, last_seen_at) VALUES (5, 1, 1)",
+            [],
+        )
+        .unwrap();
+        conn.execute("DELETE FROM entries WHERE id = 5", [])
+            .unwrap();
+        let sessions: i64 = conn
+            .query_row("SELECT COUNT(*) FROM sessions", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(sessions, 0);
+        // New ids continue after the copied ones.
+        conn.execute(
+            "INSERT INTO entries (category_id, name, launch_data) VALUES (2, 'x', 'x')",
+            [],
+        )
+        .unwrap();
+        assert_eq!(conn.last_insert_rowid(), 6);
+    }
+
+    #[test]
+    fn partly_extended_library_upgrades() {
+        let fixture = Fixture::copy("launch_type.db");
+        let mut conn = fixture.open();
+        run(&mut conn, &fixture.images).unwrap();
+        assert_eq!(version(&conn), latest_version());
+
+        let (env, hooks, runners): (String, String, Option<String>) = conn
+            .query_row(
+                "SELECT env, hooks, runners FROM entries WHERE id = 2",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!(env, r#"{"DXVK_HUD":"fps"}"#);
+        assert_eq!(hooks, "{}");
+        assert_eq!(runners, None);
+
+        let uuids: Vec<String> = conn
+            .prepare("SELECT uuid FROM entries ORDER BY id")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(uuids.len(), 2);
+        assert_ne!(uuids[0], uuids[1]);
+        assert!(uuids.iter().all(|u| Uuid::parse_str(u).is_ok()));
+    }
+
+    #[test]
+    fn data_urls_move_to_the_image_store() {
+        let fixture = Fixture::copy("launch_type.db");
+        let mut conn = fixture.open();
+        conn.execute(
+            "INSERT INTO entries (id, type, name, launch_data, image_data)
+             VALUES (3, 'app', 'No art', 'x', '/images/legacy.png')",
+            [],
+        )
+        .unwrap();
+        run(&mut conn, &fixture.images).unwrap();
+
+        let hashes: Vec<Option<String>> = conn
+            .prepare("SELECT image_hash FROM entries ORDER BY id")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        // `AAAA` and `BBBB` decode to different bytes.
+        let (first, second) = (hashes[0].clone().unwrap(), hashes[1].clone().unwrap());
+        assert_ne!(first, second);
+        assert_eq!(hashes[2], None);
+        assert_eq!(fixture.images.get(&first).unwrap(), [0, 0, 0]);
+        assert_eq!(first, images::hash(&[0, 0, 0]));
+        assert!(fixture.images.contains(&second));
+    }
+
+    #[test]
+    fn fresh_and_current_databases() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        run(&mut conn, &no_images()).unwrap();
+        assert_eq!(version(&conn), latest_version());
+        conn.execute(
+            "INSERT INTO entries (category_id, name, launch_type, launch_data)
+             VALUES (1, 'Portal 2', 'steam', '620')",
+            [],
+        )
+        .unwrap();
+
+        // Running again is a no-op.
+        run(&mut conn, &no_images()).unwrap();
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM entries", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 1);
+        let on: bool = conn
+            .pragma_query_value(None, "foreign_keys", |row| row.get(0))
+            .unwrap();
+        assert!(on);
+    }
+
+    #[test]
+    fn standalone_macros_become_entries() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.pragma_update(None, "foreign_keys", false).unwrap();
+        // Stop right before macros became entries.
+        for (version, migration) in (1..).zip(&MIGRATIONS[..6]) {
+            let tx = conn.transaction().unwrap();
+            (migration.up)(&tx, &no_images()).unwrap();
+            tx.pragma_update(None, "user_version", version).unwrap();
+            tx.commit().unwrap();
+        }
+        conn.execute_batch(
+            "INSERT INTO entries (id, uuid, category_id, name, launch_type, launch_data)
+             VALUES (1, 'a', 2, 'Discord', 'exe', '/bin/discord'),
+                    (2, 'b', 1, 'Portal 2', 'steam', '620');
+             INSERT INTO macros (id, name, mode, on_failure, created_at)
+             VALUES (7, 'Game night', 'parallel', 'continue', 1700000300);
+             INSERT INTO macro_steps (macro_id, position, entry_id, profile, delay_ms)
+             VALUES (7, 0, 1, NULL, 0), (7, 1, 2, 'Vulkan', 2000);",
+        )
+        .unwrap();
+
+        run(&mut conn, &no_images()).unwrap();
+        assert_eq!(version(&conn), latest_version());
+
+        let (id, category, launch_type, created_at, image): (
+            i64,
+            i64,
+            String,
+            i64,
+            Option<String>,
+        ) = conn
+            .query_row(
+                "SELECT id, category_id, launch_type, created_at, image_hash
+                 FROM entries WHERE name = 'Game night'",
+                [],
+                |row| {
+                    Ok((
+                        row.get(0)?,
+                        row.get(1)?,
+                        row.get(2)?,
+                        row.get(3)?,
+                        row.get(4)?,
+                    ))
+                },
+            )
+            .unwrap();
+        assert_eq!((category, launch_type.as_str()), (1, "macro"));
+        assert_eq!((created_at, image), (1700000300, None));
+        let definition = macros::get(&conn, id).unwrap().unwrap();
+        assert_eq!(definition.mode, macros::MacroMode::Parallel);
+        assert_eq!(definition.on_failure, macros::FailurePolicy::Continue);
+        let steps: Vec<(i64, Option<&str>, u64)> = definition
+            .steps
+            .iter()
+            .map(|s| (s.entry_id, s.profile.as_deref(), s.delay_ms))
+            .collect();
+        assert_eq!(steps, [(1, None, 0), (2, Some("Vulkan"), 2000)]);
+
+        // Deleting the macro's entry takes its definition with it.
+        conn.execute("DELETE FROM entries WHERE id = ?1", [id])
+            .unwrap();
+        assert_eq!(macros::get(&conn, id).unwrap(), None);
+    }
+
+    #[test]
+    fn refuses_newer_schema() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.pragma_update(None, "user_version", latest_version() + 1)
+            .unwrap();
+        assert!(run(&mut conn, &no_images())
+            .unwrap_err()
+            .contains("newer version"));
+    }
+
+    #[test]
+    fn failed_migration_rolls_back() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        // An `entries` table that migration 2 cannot extend.
+        conn.execute_batch(
+            "CREATE VIEW entries AS SELECT 1 AS id;
+             PRAGMA user_version = 1;",
+        )
+        .unwrap();
+        let error = run(&mut conn, &no_images()).unwrap_err();
+        assert!(error.starts_with("Migration 2"), "{error}");
+        assert_eq!(version(&conn), 1);
+        let tables: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE name = ?1",
+                ["settings"],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(tables, 0);
+    }
+}
@@ -0,0 +1,233 @@
+use std::thread;
+use std::time::Duration;
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use tauri::{AppHandle, Manager, State};
+
+use crate::db::{unix_now, Database};
+
+/// Tracked launches, one row per process lifetime. `last_seen_at` is bumped
+/// by a heartbeat while the session is open, so a session left open by a
+/// crash can be closed at the last moment K-Scope knew it was alive.
+pub const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS sessions (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        entry_id INTEGER NOT NULL REFERENCES entries(id) ON DELETE CASCADE,
+        started_at INTEGER NOT NULL,
+        ended_at INTEGER,
+        last_seen_at INTEGER NOT NULL,
+        interrupted INTEGER NOT NULL DEFAULT 0
+    );
+    CREATE INDEX IF NOT EXISTS sessions_by_entry ON sessions (entry_id, started_at);
+";
+
+const HEARTBEAT: Duration = Duration::from_secs(60);
+const WEEK_SECS: i64 = 7 * 24 * 60 * 60;
+
+/// Playtime totals for one entry, in seconds.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Playtime {
+    pub entry_id: i64,
+    pub total_secs: i64,
+    pub last_7_days_secs: i64,
+    /// Length of the most recent session (still growing if it is open).
+    pub last_session_secs: Option<i64>,
+    pub last_played_at: Option<i64>,
+}
+
+/// Open a session for a freshly launched entry. Returns the session id.
+pub fn start_session(conn: &Connection, entry_id: i64, now: i64) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO sessions (entry_id, started_at, last_seen_at) VALUES (?1, ?2, ?2)",
+        params![entry_id, now],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn end_session(conn: &Connection, session_id: i64, now: i64) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE sessions SET ended_at = ?2, last_seen_at = ?2 WHERE id = ?1 AND ended_at IS NULL",
+        params![session_id, now],
+    )?;
+    Ok(())
+}
+
+/// Close sessions that were still open when the previous run ended. Called
+/// once on startup, before anything new is tracked.
+pub fn close_interrupted_sessions(conn: &Connection) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE sessions SET ended_at = last_seen_at, interrupted = 1 WHERE ended_at IS NULL",
+        [],
+    )
+}
+
+fn heartbeat(conn: &Connection, now: i64) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE sessions SET last_seen_at = ?1 WHERE ended_at IS NULL",
+        params![now],
+    )?;
+    Ok(())
+}
+
+/// Keep `last_seen_at` of open sessions current for the lifetime of the app.
+pub fn spawn_heartbeat(app: AppHandle) {
+    thread::spawn(move || loop {
+        thread::sleep(HEARTBEAT);
+        let db = app.state::<Database>();
+        let _ = heartbeat(&db.conn(), unix_now());
+    });
+}
+
+/// Totals per entry, for one entry or (with `None`) every entry that has
+/// been played.
+pub fn playtime(
+    conn: &Connection,
+    entry_id: Option<i64>,
+    now: i64,
+) -> rusqlite::Result<Vec<Playtime>> {
+    let mut stmt = conn.prepare(
+        "SELECT s.entry_id,
+                SUM(COALESCE(s.ended_at, ?1) - s.started_at),
+                SUM(MAX(0, COALESCE(s.ended_at, ?1) - MAX(s.started_at, ?2))),
+                (SELECT COALESCE(l.ended_at, ?1) - l.started_at FROM sessions l
+                  WHERE l.entry_id = s.entry_id
+                  ORDER BY l.started_at DESC, l.id DESC LIMIT 1),
+                MAX(s.started_at)
+         FROM sessions s
+         WHERE ?3 IS NULL OR s.entry_id = ?3
+         GROUP BY s.entry_id
+         ORDER BY s.entry_id",
+    )?;
+
+    let rows = stmt.query_map(params![now, now - WEEK_SECS, entry_id], |row| {
+        Ok(Playtime {
+            entry_id: row.get(0)?,
+            total_secs: row.get(1)?,
+            last_7_days_secs: row.get(2)?,
+            last_session_secs: row.get(3)?,
+            last_played_at: row.get(4)?,
+        })
+    })?;
+
+    rows.collect()
+}
+
+// --- COMMANDS ---
+
+/// Playtime for a single entry. Entries that were never played report zeros.
+#[tauri::command]
+pub fn entry_playtime(db: State<'_, Database>, id: i64) -> Result<Playtime, String> {
+    let conn = db.conn();
+    let exists = conn
+        .query_row("SELECT 1 FROM entries WHERE id = ?1", params![id], |_| {
+            Ok(())
+        })
+        .optional()
+        .map_err(|e| e.to_string())?;
+    if exists.is_none() {
+        return Err(format!("Entry {id} not found"));
+    }
+
+    let found = playtime(&conn, Some(id), unix_now()).map_err(|e| e.to_string())?;
+    Ok(found.into_iter().next().unwrap_or(Playtime {
+        entry_id: id,
+        total_secs: 0,
+        last_7_days_secs: 0,
+        last_session_secs: None,
+        last_played_at: None,
+    }))
+}
+
+/// Playtime for every entry that has at least one session.
+#[tauri::command]
+pub fn library_playtime(db: State<'_, Database>) -> Result<Vec<Playtime>, String> {
+    playtime(&db.conn(), None, unix_now()).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: i64 = 24 * 60 * 60;
+    const NOW: i64 = 100 * DAY;
+
+    fn conn() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE entries (id INTEGER PRIMARY KEY); INSERT INTO entries VALUES (1), (2);",
+        )
+        .unwrap();
+        conn.execute_batch(SCHEMA).unwrap();
+        conn
+    }
+
+    fn session(conn: &Connection, entry_id: i64, start: i64, end: Option<i64>) {
+        conn.execute(
+            "INSERT INTO sessions (entry_id, started_at, ended_at, last_seen_at)
+             VALUES (?1, ?2, ?3, COALESCE(?3, ?2))",
+            params![entry_id, start, end],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn totals_and_last_week() {
+        let conn = conn();
+        // Ten days ago, one hour.
+        session(&conn, 1, NOW - 10 * DAY, Some(NOW - 10 * DAY + 3600));
+        // Straddles the 7-day boundary: only the second half counts.
+        session(&conn, 1, NOW - 7 * DAY - 1800, Some(NOW - 7 * DAY + 1800));
+        // Yesterday, two hours.
+        session(&conn, 1, NOW - DAY, Some(NOW - DAY + 7200));
+        session(&conn, 2, NOW - 60, Some(NOW - 30));
+
+        let all = playtime(&conn, None, NOW).unwrap();
+        assert_eq!(
+            all[0],
+            Playtime {
+                entry_id: 1,
+                total_secs: 3600 + 3600 + 7200,
+                last_7_days_secs: 1800 + 7200,
+                last_session_secs: Some(7200),
+                last_played_at: Some(NOW - DAY),
+            }
+        );
+        assert_eq!(all[1].total_secs, 30);
+        assert_eq!(playtime(&conn, Some(2), NOW).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn open_session_counts_up_to_now() {
+        let conn = conn();
+        let id = start_session(&conn, 1, NOW - 600).unwrap();
+
+        let open = &playtime(&conn, Some(1), NOW).unwrap()[0];
+        assert_eq!(open.total_secs, 600);
+        assert_eq!(open.last_session_secs, Some(600));
+
+        end_session(&conn, id, NOW - 100).unwrap();
+        let closed = &playtime(&conn, Some(1), NOW).unwrap()[0];
+        assert_eq!(closed.total_secs, 500);
+    }
+
+    #[test]
+    fn interrupted_sessions_close_at_last_heartbeat() {
+        let conn = conn();
+        let id = start_session(&conn, 1, NOW - 600).unwrap();
+        heartbeat(&conn, NOW - 240).unwrap();
+
+        assert_eq!(close_interrupted_sessions(&conn).unwrap(), 1);
+
+        let (ended_at, interrupted): (i64, bool) = conn
+            .query_row(
+                "SELECT ended_at, interrupted FROM sessions WHERE id = ?1",
+                params![id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(ended_at, NOW - 240);
+        assert!(interrupted);
+        assert_eq!(playtime(&conn, Some(1), NOW).unwrap()[0].total_secs, 360);
+    }
+}
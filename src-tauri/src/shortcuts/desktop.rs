@@ -0,0 +1,269 @@
+use std::env;
+use std::path::{Path, PathBuf};
+
+use super::Shortcut;
+use crate::launcher::{args, expand};
+
+/// Parse a freedesktop `.desktop` file. Only the `[Desktop Entry]` group
+/// and untranslated keys are read. `Application` entries give the program
+/// and arguments from `Exec`, `Link` entries give their `URL`. Desktop
+/// files expand no variables, so the program, arguments and `Path` are
+/// escaped to stay literal (see [`expand::literal`]).
+pub fn parse(text: &str) -> Result<Shortcut, String> {
+    let mut in_entry = false;
+    let mut fields = Vec::new();
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line.starts_with('[') {
+            in_entry = line == "[Desktop Entry]";
+            continue;
+        }
+        if !in_entry {
+            continue;
+        }
+        if let Some((key, value)) = line.split_once('=') {
+            fields.push((key.trim(), unescape(value.trim())));
+        }
+    }
+    let field = |key: &str| {
+        fields
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| v.clone())
+            .filter(|v| !v.is_empty())
+    };
+
+    let (target, arguments) = match field("Type").as_deref() {
+        Some("Application") => {
+            let exec = field("Exec").ok_or("Desktop entry has no Exec line")?;
+            let mut argv = exec_args(&exec)?.into_iter();
+            let program = argv.next().ok_or("Desktop entry has an empty Exec line")?;
+            let argv: Vec<String> = argv.map(|arg| expand::literal(&arg)).collect();
+            (expand::literal(&program), args::join_posix(&argv))
+        }
+        Some("Link") => (
+            field("URL").ok_or("Desktop entry has no URL")?,
+            String::new(),
+        ),
+        Some(other) => return Err(format!("Unsupported desktop entry type: {other}")),
+        None if field("Name").is_some() => return Err("Desktop entry has no Type".into()),
+        None => return Err("Not a desktop entry file".into()),
+    };
+
+    Ok(Shortcut {
+        name: field("Name"),
+        target,
+        arguments,
+        working_dir: field("Path").map(|path| expand::literal(&path)),
+        icon: field("Icon"),
+    })
+}
+
+/// Undo the escapes allowed in string values: `\s`, `\n`, `\t`, `\r` and
+/// `\\`.
+fn unescape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('s') => out.push(' '),
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some('r') => out.push('\r'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// Split an `Exec` value into program and arguments. Quoted arguments use
+/// `"..."` with `\"`, `` \` ``, `\$` and `\\` escapes. The field codes
+/// `%f %F %u %U %i %c %k` stand for files, URLs or the entry's own icon,
+/// name and location and are dropped; `%%` is a literal `%`. Any other `%`
+/// is kept as written.
+fn exec_args(exec: &str) -> Result<Vec<String>, String> {
+    let mut out = Vec::new();
+    let mut current = String::new();
+    let mut started = false;
+    let mut chars = exec.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            ' ' | '\t' => {
+                if started {
+                    out.push(std::mem::take(&mut current));
+                    started = false;
+                }
+            }
+            '"' => {
+                loop {
+                    match chars.next() {
+                        None => return Err(format!("Unclosed quote in Exec line: {exec}")),
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '`' | '$' | '\\')) => current.push(c),
+                            Some(c) => {
+                                current.push('\\');
+                                current.push(c);
+                            }
+                            None => return Err(format!("Unclosed quote in Exec line: {exec}")),
+                        },
+                        Some(c) => current.push(c),
+                    }
+                }
+                started = true;
+            }
+            '%' => match chars.clone().next() {
+                Some('f' | 'F' | 'u' | 'U' | 'i' | 'c' | 'k') => {
+                    chars.next();
+                }
+                Some('%') => {
+                    chars.next();
+                    current.push('%');
+                    started = true;
+                }
+                _ => {
+                    current.push('%');
+                    started = true;
+                }
+            },
+            c => {
+                current.push(c);
+                started = true;
+            }
+        }
+    }
+    if started {
+        out.push(current);
+    }
+    Ok(out)
+}
+
+/// Find the file for a themed icon name such as `steam_icon_620`, looking
+/// in the hicolor theme (largest size first) and `pixmaps` of each data
+/// dir. Paths are returned unchanged.
+pub fn find_icon(icon: &str, data_dirs: &[PathBuf]) -> Option<PathBuf> {
+    if icon.contains('/') {
+        return Some(PathBuf::from(icon));
+    }
+    const SIZES: [&str; 6] = ["512x512", "256x256", "128x128", "96x96", "64x64", "48x48"];
+    let candidates = |dir: &Path| {
+        let hicolor = SIZES
+            .iter()
+            .map(|size| dir.join(format!("icons/hicolor/{size}/apps/{icon}.png")));
+        let pixmaps = ["png", "jpg"].map(|ext| dir.join(format!("pixmaps/{icon}.{ext}")));
+        hicolor.chain(pixmaps).collect::<Vec<_>>()
+    };
+    data_dirs
+        .iter()
+        .flat_map(|dir| candidates(dir))
+        .find(|path| path.is_file())
+}
+
+/// `$XDG_DATA_HOME` followed by `$XDG_DATA_DIRS`, with the spec's defaults.
+pub fn data_dirs() -> Vec<PathBuf> {
+    let home = env::var_os("XDG_DATA_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")));
+    let system = env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".into());
+    home.into_iter()
+        .chain(system.split(':').map(PathBuf::from))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GAME: &str = include_str!("../../fixtures/shortcuts/game.desktop");
+    const STEAM: &str = include_str!("../../fixtures/shortcuts/steam.desktop");
+    const WIKI: &str = include_str!("../../fixtures/shortcuts/wiki.desktop");
+
+    #[test]
+    fn application_entry() {
+        assert_eq!(
+            parse(GAME).unwrap(),
+            Shortcut {
+                name: Some("Dwarf Fortress".into()),
+                target: "/opt/Dwarf Fortress/dwarfort".into(),
+                arguments: r#"--mode 'fortress "deluxe"'"#.into(),
+                working_dir: Some("/opt/Dwarf Fortress".into()),
+                icon: Some("/opt/Dwarf Fortress/icon.png".into()),
+            }
+        );
+    }
+
+    #[test]
+    fn steam_and_link_entries() {
+        let steam = parse(STEAM).unwrap();
+        assert_eq!(steam.target, "steam");
+        assert_eq!(steam.arguments, "steam://rungameid/620");
+        assert_eq!(steam.icon.as_deref(), Some("steam_icon_620"));
+
+        let wiki = parse(WIKI).unwrap();
+        assert_eq!(wiki.name.as_deref(), Some("Portal Wiki"));
+        assert_eq!(wiki.target, "https://theportalwiki.com/wiki/Main_Page");
+        assert_eq!(wiki.arguments, "");
+    }
+
+    #[test]
+    fn exec_lines() {
+        let cases: &[(&str, &[&str])] = &[
+            ("firefox %u", &["firefox"]),
+            ("app --file=%f --x", &["app", "--file=", "--x"]),
+            ("app 100%%", &["app", "100%"]),
+            ("app 50% done %x", &["app", "50%", "done", "%x"]),
+            ("app % --flag", &["app", "%", "--flag"]),
+            ("app %c%k%i", &["app"]),
+            (
+                r#""/opt/My App/run" "a \$b \\ \`c\`""#,
+                &["/opt/My App/run", "a $b \\ `c`"],
+            ),
+            (r#"sh -c "echo \"hi\"""#, &["sh", "-c", r#"echo "hi""#]),
+            ("  spaced\t out  ", &["spaced", "out"]),
+        ];
+        for (exec, expected) in cases {
+            assert_eq!(exec_args(exec).unwrap(), *expected, "exec: {exec:?}");
+        }
+        assert!(exec_args(r#"app "open"#).is_err());
+    }
+
+    #[test]
+    fn literal_text_is_escaped() {
+        let shortcut = parse(
+            "[Desktop Entry]\nType=Application\nName=Tool\n\
+             Exec=/opt/$tool/run --price=$5 ~/save %%PATH%% %f\nPath=/srv/$data\n",
+        )
+        .unwrap();
+        assert_eq!(shortcut.target, "/opt/$$tool/run");
+        assert_eq!(shortcut.working_dir.as_deref(), Some("/srv/$$data"));
+        let argv: Vec<String> = args::split_posix(&shortcut.arguments)
+            .unwrap()
+            .iter()
+            .map(|arg| expand::expand_with(arg, |_| None, Some("/home/me")).unwrap())
+            .collect();
+        assert_eq!(argv, ["--price=$5", "~/save", "%PATH%"]);
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        assert_eq!(
+            parse("[Desktop Entry]\nType=Directory\nName=Games\n").unwrap_err(),
+            "Unsupported desktop entry type: Directory"
+        );
+        assert!(parse("[Other]\nExec=app\n").is_err());
+        assert!(parse("[Desktop Entry]\nType=Application\nName=x\n").is_err());
+    }
+}
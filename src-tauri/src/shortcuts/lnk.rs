@@ -0,0 +1,274 @@
+use super::Shortcut;
+
+const HEADER_SIZE: u32 = 0x4C;
+const LINK_CLSID: [u8; 16] = [
+    0x01, 0x14, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0xC0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x46,
+];
+
+// LinkFlags
+const HAS_LINK_TARGET_ID_LIST: u32 = 0x1;
+const HAS_LINK_INFO: u32 = 0x2;
+const HAS_NAME: u32 = 0x4;
+const HAS_RELATIVE_PATH: u32 = 0x8;
+const HAS_WORKING_DIR: u32 = 0x10;
+const HAS_ARGUMENTS: u32 = 0x20;
+const HAS_ICON_LOCATION: u32 = 0x40;
+const IS_UNICODE: u32 = 0x80;
+const FORCE_NO_LINK_INFO: u32 = 0x100;
+
+// LinkInfoFlags
+const VOLUME_ID_AND_LOCAL_BASE_PATH: u32 = 0x1;
+const COMMON_NETWORK_RELATIVE_LINK: u32 = 0x2;
+
+// ExtraData block signatures
+const ENVIRONMENT_BLOCK: u32 = 0xA000_0001;
+const ICON_ENVIRONMENT_BLOCK: u32 = 0xA000_0007;
+
+const TRUNCATED: &str = "Shortcut file is truncated";
+
+/// Parse the contents of a Windows Shell Link (`.lnk`) file, as laid out in
+/// MS-SHLLINK. Only what a launch needs is read: the target from the
+/// LinkInfo structure or an environment variable block, and the working
+/// directory, arguments and icon location; the shell item ID list is
+/// skipped. A target given only as a relative path is returned as such
+/// (`.\game.exe`). Windows expands `%VAR%` in shortcuts, so that is kept,
+/// but a `$` is literal and doubled for the launcher.
+pub fn parse(data: &[u8]) -> Result<Shortcut, String> {
+    let mut r = Reader { data, pos: 0 };
+    if r.u32()? != HEADER_SIZE || r.take(16)? != LINK_CLSID {
+        return Err("Not a Windows shortcut (.lnk) file".into());
+    }
+    let flags = r.u32()?;
+    r.take(HEADER_SIZE as usize - r.pos)?;
+
+    if flags & HAS_LINK_TARGET_ID_LIST != 0 {
+        let size = r.u16()?;
+        r.take(size as usize)?;
+    }
+
+    let mut target = None;
+    if flags & HAS_LINK_INFO != 0 {
+        let size = u32_at(data, r.pos)?;
+        let info = r.take(size as usize)?;
+        if flags & FORCE_NO_LINK_INFO == 0 {
+            target = link_info_path(info)?;
+        }
+    }
+
+    let unicode = flags & IS_UNICODE != 0;
+    let mut string = |flag: u32| -> Result<Option<String>, String> {
+        if flags & flag == 0 {
+            return Ok(None);
+        }
+        let chars = r.u16()? as usize;
+        let s = if unicode {
+            utf16(r.take(chars * 2)?)
+        } else {
+            ansi(r.take(chars)?)
+        };
+        Ok(Some(s))
+    };
+    // The name is a description ("Comment" in the properties dialog), not a
+    // display name; the file name is used for that instead.
+    string(HAS_NAME)?;
+    let relative_path = string(HAS_RELATIVE_PATH)?;
+    let working_dir = string(HAS_WORKING_DIR)?;
+    let arguments = string(HAS_ARGUMENTS)?;
+    let mut icon = string(HAS_ICON_LOCATION)?;
+
+    // Trailing blocks; a size under 4 is the terminal block.
+    while let Ok(size) = r.u32() {
+        if size < 4 {
+            break;
+        }
+        let block = r.take(size as usize - 4)?;
+        let signature = u32_at(block, 0)?;
+        if signature != ENVIRONMENT_BLOCK && signature != ICON_ENVIRONMENT_BLOCK {
+            continue;
+        }
+        // TargetAnsi[260] followed by TargetUnicode[520].
+        let unicode = block.get(264..784).map(utf16).filter(|s| !s.is_empty());
+        let value = unicode.or_else(|| block.get(4..264).map(ansi));
+        match signature {
+            ENVIRONMENT_BLOCK if target.is_none() => target = value,
+            ICON_ENVIRONMENT_BLOCK => icon = value.or(icon),
+            _ => {}
+        }
+    }
+
+    let target = target
+        .or(relative_path)
+        .filter(|t| !t.is_empty())
+        .ok_or("Shortcut has no target path; it may point at a special folder")?;
+    let literal_dollars = |s: String| s.replace('$', "$$");
+    Ok(Shortcut {
+        name: None,
+        target: literal_dollars(target),
+        arguments: arguments.map(literal_dollars).unwrap_or_default(),
+        working_dir: working_dir.filter(|d| !d.is_empty()).map(literal_dollars),
+        icon: icon.filter(|i| !i.is_empty()),
+    })
+}
+
+/// The target path stored in a LinkInfo structure: a local path, or a
+/// network share joined with the common path suffix.
+fn link_info_path(info: &[u8]) -> Result<Option<String>, String> {
+    let header_size = u32_at(info, 4)?;
+    let flags = u32_at(info, 8)?;
+    // Unicode offsets only exist in headers of 0x24 bytes or more.
+    let has_unicode = header_size >= 0x24;
+    let string = |ansi_at: usize, unicode_at: usize| -> Result<String, String> {
+        if has_unicode {
+            let offset = u32_at(info, unicode_at)? as usize;
+            if offset != 0 {
+                return wide_cstr(info, offset);
+            }
+        }
+        cstr(info, u32_at(info, ansi_at)? as usize)
+    };
+    let suffix = string(24, 32)?;
+
+    if flags & VOLUME_ID_AND_LOCAL_BASE_PATH != 0 {
+        return Ok(Some(string(16, 28)? + &suffix));
+    }
+    if flags & COMMON_NETWORK_RELATIVE_LINK != 0 {
+        let link = u32_at(info, 20)? as usize;
+        let net_name_offset = u32_at(info, link + 8)? as usize;
+        let share = if net_name_offset > 0x14 {
+            wide_cstr(info, link + u32_at(info, link + 20)? as usize)?
+        } else {
+            cstr(info, link + net_name_offset)?
+        };
+        if suffix.is_empty() {
+            return Ok(Some(share));
+        }
+        return Ok(Some(format!("{}\\{suffix}", share.trim_end_matches('\\'))));
+    }
+    Ok(None)
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], String> {
+        let bytes = self.data.get(self.pos..self.pos + n).ok_or(TRUNCATED)?;
+        self.pos += n;
+        Ok(bytes)
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let value = u32_at(self.data, self.pos)?;
+        self.pos += 4;
+        Ok(value)
+    }
+}
+
+fn u32_at(data: &[u8], at: usize) -> Result<u32, String> {
+    let bytes = data.get(at..at + 4).ok_or(TRUNCATED)?;
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// NUL-terminated string in the system code page, read as Latin-1.
+fn cstr(data: &[u8], at: usize) -> Result<String, String> {
+    let rest = data.get(at..).ok_or(TRUNCATED)?;
+    Ok(ansi(rest))
+}
+
+fn wide_cstr(data: &[u8], at: usize) -> Result<String, String> {
+    let rest = data.get(at..).ok_or(TRUNCATED)?;
+    Ok(utf16(rest))
+}
+
+/// Bytes up to the first NUL, read as Latin-1.
+fn ansi(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .take_while(|&&b| b != 0)
+        .map(|&b| b as char)
+        .collect()
+}
+
+/// UTF-16LE up to the first NUL.
+fn utf16(bytes: &[u8]) -> String {
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .take_while(|&unit| unit != 0)
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PORTAL: &[u8] = include_bytes!("../../fixtures/shortcuts/portal.lnk");
+    const BACKUP: &[u8] = include_bytes!("../../fixtures/shortcuts/backup.lnk");
+    const QUAKE: &[u8] = include_bytes!("../../fixtures/shortcuts/quake.lnk");
+
+    #[test]
+    fn unicode_link_with_local_target() {
+        assert_eq!(
+            parse(PORTAL).unwrap(),
+            Shortcut {
+                name: None,
+                target: r"C:\Games\Portal\portal.exe".into(),
+                arguments: r#"-novid -console +map "test chamber""#.into(),
+                working_dir: Some(r"C:\Games\Portal".into()),
+                icon: Some(r"C:\Games\Portal\portal.ico".into()),
+            }
+        );
+    }
+
+    #[test]
+    fn ansi_link_with_environment_target() {
+        assert_eq!(
+            parse(BACKUP).unwrap(),
+            Shortcut {
+                name: None,
+                target: r"%USERPROFILE%\Scripts\backup.bat".into(),
+                arguments: String::new(),
+                working_dir: Some(r"%USERPROFILE%\Scripts".into()),
+                icon: Some(r"%SystemRoot%\system32\shell32.dll".into()),
+            }
+        );
+    }
+
+    #[test]
+    fn network_share_target() {
+        let shortcut = parse(QUAKE).unwrap();
+        assert_eq!(shortcut.target, r"\\nas\games\Quake\quake.exe");
+        assert_eq!(shortcut.arguments, "-game ctf");
+        assert_eq!(shortcut.working_dir, None);
+    }
+
+    #[test]
+    fn dollars_are_literal() {
+        // `-novid` becomes `$novid`, in UTF-16.
+        let needle = [b'-', 0, b'n', 0, b'o', 0];
+        let at = PORTAL.windows(6).position(|w| w == needle).unwrap();
+        let mut data = PORTAL.to_vec();
+        data[at] = b'$';
+        let shortcut = parse(&data).unwrap();
+        assert!(
+            shortcut.arguments.starts_with("$$novid "),
+            "{}",
+            shortcut.arguments
+        );
+    }
+
+    #[test]
+    fn rejects_other_and_truncated_files() {
+        assert!(parse(b"[Desktop Entry]\nName=x\n").is_err());
+        assert_eq!(parse(&PORTAL[..120]).unwrap_err(), TRUNCATED);
+        assert_eq!(parse(&PORTAL[..60]).unwrap_err(), TRUNCATED);
+    }
+}
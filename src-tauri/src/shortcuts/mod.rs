@@ -0,0 +1,236 @@
+use std::fs;
+use std::path::Path;
+
+use serde::Serialize;
+use tauri::State;
+
+use crate::db::Database;
+use crate::image_pipeline;
+use crate::images::ImageStore;
+use crate::launcher::{steam, LaunchType};
+
+pub mod desktop;
+pub mod lnk;
+
+/// What a shortcut file points at, before it is turned into an entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Shortcut {
+    /// Display name, if the file carries one.
+    pub name: Option<String>,
+    /// Program path or URL.
+    pub target: String,
+    /// In the launch argument syntax of the platform the file comes from:
+    /// a Windows command line for `.lnk`, POSIX words for `.desktop`.
+    pub arguments: String,
+    pub working_dir: Option<String>,
+    /// Icon path, or a themed icon name for `.desktop` files.
+    pub icon: Option<String>,
+}
+
+/// A ready-to-save entry built from a shortcut. The fields match the
+/// `entries` columns of the same name.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ImportedEntry {
+    pub name: String,
+    pub launch_type: LaunchType,
+    pub launch_data: String,
+    pub launch_args: String,
+    /// Empty for the folder of the target.
+    pub working_dir: String,
+    /// Icon file, which may be an `.ico`, `.exe` or `.dll` for Windows
+    /// shortcuts. `None` if the shortcut has none.
+    pub icon_path: Option<String>,
+    /// The icon in the image store, when it is an image file the UI can
+    /// show. Filled in by `import_shortcut`.
+    pub image_hash: Option<String>,
+}
+
+/// Read a `.lnk` or `.desktop` file.
+pub fn read(path: &Path) -> Result<Shortcut, String> {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_ascii_lowercase);
+    let read_error = |e: std::io::Error| format!("Could not read {}: {e}", path.display());
+    match extension.as_deref() {
+        Some("lnk") => lnk::parse(&fs::read(path).map_err(read_error)?),
+        Some("desktop") => {
+            let mut shortcut = desktop::parse(&fs::read_to_string(path).map_err(read_error)?)?;
+            shortcut.icon = shortcut.icon.map(|icon| {
+                desktop::find_icon(&icon, &desktop::data_dirs())
+                    .map_or(icon, |found| found.to_string_lossy().into_owned())
+            });
+            Ok(shortcut)
+        }
+        _ => Err("Only .lnk and .desktop shortcuts can be imported".into()),
+    }
+}
+
+/// Turn a shortcut found at `path` into an entry. Steam URLs become Steam
+/// entries, other URLs URL entries, scripts script entries and anything
+/// else an executable. Relative targets are resolved against the folder of
+/// the shortcut.
+pub fn to_entry(path: &Path, shortcut: Shortcut) -> ImportedEntry {
+    let name = shortcut.name.clone().unwrap_or_else(|| {
+        path.file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    });
+    let icon_path = shortcut.icon.clone();
+    let entry = |launch_type, launch_data: String, launch_args: String| ImportedEntry {
+        name: name.clone(),
+        launch_type,
+        launch_data,
+        launch_args,
+        working_dir: shortcut.working_dir.clone().unwrap_or_default(),
+        icon_path: icon_path.clone(),
+        image_hash: None,
+    };
+
+    let steam_app = steam::app_id_from_url(&shortcut.target).or_else(|| {
+        // `steam steam://rungameid/620`, as written by the Linux client.
+        let program = shortcut.target.rsplit(['/', '\\']).next()?;
+        if !program.eq_ignore_ascii_case("steam") && !program.eq_ignore_ascii_case("steam.exe") {
+            return None;
+        }
+        shortcut
+            .arguments
+            .split_whitespace()
+            .find_map(|arg| steam::app_id_from_url(arg.trim_matches(['"', '\''])))
+    });
+    if let Some(app_id) = steam_app {
+        return entry(LaunchType::Steam, app_id.to_string(), String::new());
+    }
+    if is_url(&shortcut.target) {
+        return entry(LaunchType::Url, shortcut.target.clone(), String::new());
+    }
+
+    let target = match shortcut.target.strip_prefix(".\\") {
+        Some(rest) => path.with_file_name(rest).to_string_lossy().into_owned(),
+        None if shortcut.target.starts_with("./") || shortcut.target.starts_with("..") => path
+            .with_file_name(&shortcut.target)
+            .to_string_lossy()
+            .into_owned(),
+        None => shortcut.target.clone(),
+    };
+    let extension = target
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_ascii_lowercase())
+        .unwrap_or_default();
+    let launch_type = match extension.as_str() {
+        "bat" | "cmd" | "sh" => LaunchType::Bat,
+        _ => LaunchType::Exe,
+    };
+    entry(launch_type, target, shortcut.arguments.clone())
+}
+
+/// A `scheme://` URL. Drive letters (`C:\`) are single characters and do
+/// not count.
+fn is_url(target: &str) -> bool {
+    target.split_once("://").is_some_and(|(scheme, _)| {
+        scheme.len() > 1
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+    })
+}
+
+// --- COMMANDS ---
+
+/// Parse a `.lnk` or `.desktop` file into an entry the add form can save.
+/// An icon that is a plain image is copied into the image store; others,
+/// such as `.ico` or `.exe` icons, are left for the user to replace.
+#[tauri::command]
+pub fn import_shortcut(
+    db: State<'_, Database>,
+    images: State<'_, ImageStore>,
+    path: String,
+) -> Result<ImportedEntry, String> {
+    let path = Path::new(&path);
+    let mut entry = to_entry(path, read(path)?);
+    let settings = image_pipeline::load_settings(&db.conn()).map_err(|e| e.to_string())?;
+    entry.image_hash = entry
+        .icon_path
+        .as_deref()
+        .and_then(|icon| image_pipeline::import(&images, Path::new(icon), &settings).ok());
+    Ok(entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn import(file: &str) -> ImportedEntry {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures/shortcuts")
+            .join(file);
+        to_entry(&path, read(&path).unwrap())
+    }
+
+    #[test]
+    fn windows_shortcuts() {
+        assert_eq!(
+            import("portal.lnk"),
+            ImportedEntry {
+                name: "portal".into(),
+                launch_type: LaunchType::Exe,
+                launch_data: r"C:\Games\Portal\portal.exe".into(),
+                launch_args: r#"-novid -console +map "test chamber""#.into(),
+                working_dir: r"C:\Games\Portal".into(),
+                icon_path: Some(r"C:\Games\Portal\portal.ico".into()),
+                image_hash: None,
+            }
+        );
+        let backup = import("backup.lnk");
+        assert_eq!(backup.launch_type, LaunchType::Bat);
+        assert_eq!(backup.launch_data, r"%USERPROFILE%\Scripts\backup.bat");
+    }
+
+    #[test]
+    fn desktop_entries() {
+        let game = import("game.desktop");
+        assert_eq!(game.name, "Dwarf Fortress");
+        assert_eq!(game.launch_type, LaunchType::Exe);
+        assert_eq!(game.launch_data, "/opt/Dwarf Fortress/dwarfort");
+        assert_eq!(game.working_dir, "/opt/Dwarf Fortress");
+
+        let steam = import("steam.desktop");
+        assert_eq!(
+            (
+                steam.name.as_str(),
+                steam.launch_type,
+                steam.launch_data.as_str()
+            ),
+            ("Portal 2", LaunchType::Steam, "620")
+        );
+        assert_eq!(steam.launch_args, "");
+
+        let wiki = import("wiki.desktop");
+        assert_eq!(wiki.launch_type, LaunchType::Url);
+        assert_eq!(wiki.launch_data, "https://theportalwiki.com/wiki/Main_Page");
+    }
+
+    #[test]
+    fn relative_targets_and_urls() {
+        let shortcut = |target: &str| Shortcut {
+            name: None,
+            target: target.into(),
+            arguments: String::new(),
+            working_dir: None,
+            icon: None,
+        };
+        let path = Path::new("/home/me/Desktop/Game.lnk");
+        let entry = to_entry(path, shortcut(r".\game.exe"));
+        assert_eq!(entry.name, "Game");
+        assert_eq!(entry.launch_data, "/home/me/Desktop/game.exe");
+        assert_eq!(
+            to_entry(path, shortcut("steam://rungameid/220")).launch_type,
+            LaunchType::Steam
+        );
+        assert_eq!(
+            to_entry(path, shortcut(r"C:\x.exe")).launch_type,
+            LaunchType::Exe
+        );
+        assert!(read(Path::new("notes.txt")).is_err());
+    }
+}
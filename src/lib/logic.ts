@@ -1,8 +1,8 @@
-import { Command } from "@tauri-apps/plugin-shell";
-import { open as openDialog } from "@tauri-apps/plugin-dialog";
-import { readFile } from "@tauri-apps/plugin-fs";
+import { convertFileSrc, invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+import { open as openDialog, save as saveDialog } from "@tauri-apps/plugin-dialog";
 import { getCurrentWindow } from "@tauri-apps/api/window";
-import type { Entry, LaunchType } from "../routes/db";
+import type { Entry, ExportFormat, LaunchType } from "../routes/db";
 
 // --- SOUND MANAGER ---
 /**
@@ -54,126 +54,414 @@ export async function toggleWindow(isOpen: boolean) {
 // --- LAUNCH SYSTEM ---
 
 /**
- * Launch an entry based on its launch_type.
- * Handles Steam, Exe, URL, and Batch files.
+ * What the Rust launcher did with a launch request.
+ * `already_running` means a single-instance entry is still alive; nothing was started.
  */
-export async function launchEntry(entry: Entry): Promise<boolean> {
-  try {
-    playSound("launch");
-
-    switch (entry.launch_type) {
-      case "steam":
-        await launchSteam(entry.launch_data);
-        break;
-      
-      case "exe":
-        await launchExe(entry.launch_data, entry.launch_args);
-        break;
-      
-      case "url":
-        await launchUrl(entry.launch_data);
-        break;
-      
-      case "bat":
-        await launchBat(entry.launch_data);
-        break;
-      
-      default:
-        console.error("Unknown launch type:", entry.launch_type);
-        return false;
-    }
+export type LaunchResult =
+  | { status: "launched"; entry_id: number; pid: number }
+  | { status: "already_running"; entry_id: number; pid: number }
+  | { status: "macro"; entry_id: number; steps: StepReport[] };  // one report per step, in step order
+
+/**
+ * Launch an entry through the native `launch_entry` command.
+ * The Rust side resolves Steam, Exe, URL, and Batch entries, and runs the steps of a macro.
+ * Without a profile name the entry's default profile is used.
+ * `force` starts another copy of a single-instance entry.
+ * Rejects with a LaunchError if nothing was started.
+ */
+export async function launchEntry(
+  entry: Entry,
+  profile: string | null = null,
+  force = false
+): Promise<LaunchResult> {
+  playSound("launch");
 
-    // Close window after successful launch
+  const result = await invoke<LaunchResult>("launch_entry", { id: entry.id, profile, force });
+
+  // Close window after successful launch
+  if (result.status === "launched" || (result.status === "macro" && macroSucceeded(result.steps))) {
     setTimeout(async () => {
       await toggleWindow(true);
     }, 200);
+  }
 
-    return true;
-  } 
-  catch (error) {
-    console.error("Failed to launch:", error);
-    return false;
+  return result;
+}
+
+/**
+ * Why a launch failed (see `LaunchError` in Rust)
+ */
+export type LaunchError =
+  | { kind: "entry_not_found"; id: number }
+  | { kind: "profile_not_found"; name: string }
+  | { kind: "not_found"; path: string }
+  | { kind: "permission_denied"; path: string }
+  | { kind: "invalid_steam_id"; app_id: string }
+  | { kind: "invalid_args"; error: ArgsError }
+  | { kind: "undefined_variable"; name: string }
+  | { kind: "invalid_runner"; command: string; reason: string }
+  | { kind: "spawn_failed"; reason: string; message: string }
+  | { kind: "hook_failed"; command: string; code: number | null; message: string }
+  | { kind: "timeout"; command: string; secs: number }
+  | { kind: "steam_not_started"; secs: number }
+  | { kind: "nested_macro"; name: string }
+  | { kind: "macro_has_no_command"; name: string }
+  | { kind: "macro_not_found"; id: number }
+  | { kind: "database"; message: string };
+
+/**
+ * A message for the user explaining a failed launch
+ */
+export function launchErrorMessage(error: unknown): string {
+  if (typeof error === "string") return error;
+  if (typeof error !== "object" || error === null || !("kind" in error)) {
+    return "Launch failed for an unknown reason.";
+  }
+
+  const e = error as LaunchError;
+  switch (e.kind) {
+    case "entry_not_found":
+      return "This entry no longer exists in the library.";
+    case "profile_not_found":
+      return `The launch profile "${e.name}" no longer exists.`;
+    case "not_found":
+      return `Could not find ${e.path}. Was it moved or uninstalled?`;
+    case "permission_denied":
+      return `${e.path} is not allowed to run. Check that it is executable.`;
+    case "invalid_steam_id":
+      return `"${e.app_id}" is not a valid Steam App ID. It should only contain digits.`;
+    case "invalid_args":
+      return `The launch arguments ${argsErrorMessage(e.error)}.`;
+    case "undefined_variable":
+      return e.name === "HOME"
+        ? "A path starts with ~, but no home folder is set."
+        : `The environment variable ${e.name} is not set on this machine.`;
+    case "invalid_runner":
+      return `The runner \`${e.command}\` is invalid: ${e.reason}.`;
+    case "spawn_failed":
+      return `The program could not be started: ${e.message}`;
+    case "hook_failed":
+      return e.code === null
+        ? `The pre-launch command \`${e.command}\` failed: ${e.message}`
+        : `The pre-launch command \`${e.command}\` exited with code ${e.code}.`;
+    case "timeout":
+      return `The pre-launch command \`${e.command}\` did not finish within ${e.secs}s.`;
+    case "steam_not_started":
+      return `Steam was started in the background but did not come up within ${e.secs}s.`;
+    case "nested_macro":
+      return `${e.name} is a macro and cannot be a step of another macro.`;
+    case "macro_has_no_command":
+      return `${e.name} is a macro. It runs its steps, so there is no single command to show.`;
+    case "macro_not_found":
+      return "This macro has no saved steps. Edit it and add at least one.";
+    case "database":
+      return `The library could not be read: ${e.message}`;
   }
 }
 
 /**
- * Launch a Steam game by App ID
- * Example: launchSteam("1245620") opens Monster Hunter Rise
+ * Why launch arguments could not be parsed (see `ArgsError` in Rust)
  */
-async function launchSteam(appId: string): Promise<void> {
-  const command = Command.create("cmd", [
-    "/c", 
-    "start", 
-    `steam://rungameid/${appId}`
-  ]);
-  await command.execute();
+export type ArgsError =
+  | { kind: "unbalanced_quote"; quote: string; position: number }
+  | { kind: "trailing_escape"; position: number };
+
+/**
+ * Describe an ArgsError, phrased to follow "Arguments ..."
+ */
+function argsErrorMessage(e: ArgsError): string {
+  switch (e?.kind) {
+    case "unbalanced_quote":
+      return `have an unclosed ${e.quote} quote (position ${e.position + 1})`;
+    case "trailing_escape":
+      return "end with a backslash that escapes nothing";
+    default:
+      return "could not be parsed";
+  }
 }
 
 /**
- * Launch an executable with optional arguments
+ * Split launch arguments with the same rules the launcher uses.
+ * Throws an ArgsError on malformed input.
  */
-async function launchExe(exePath: string, args: string): Promise<void> {
-  // Parse args - split by spaces but respect quoted strings
-  const argList = args.trim() ? parseArgs(args) : [];
-  
-  // Use cmd /c start to launch detached
-  const command = Command.create("cmd", [
-    "/c",
-    "start",
-    "",  // Empty title (required for start when path has spaces)
-    exePath,
-    ...argList
-  ]);
-  await command.execute();
+export async function parseLaunchArgs(args: string): Promise<string[]> {
+  return invoke<string[]>("parse_launch_args", { args });
 }
 
 /**
- * Launch a URL in default browser
+ * Check launch arguments, returning a user-facing message if they are malformed
  */
-async function launchUrl(url: string): Promise<void> {
-  const command = Command.create("cmd", [
-    "/c",
-    "start",
-    url
-  ]);
-  await command.execute();
+export async function validateLaunchArgs(args: string): Promise<string | null> {
+  try {
+    await parseLaunchArgs(args);
+    return null;
+  } catch (error) {
+    return `Arguments ${argsErrorMessage(error as ArgsError)}.`;
+  }
 }
 
+// --- LAUNCH PREVIEW ---
+
 /**
- * Launch a batch file (legacy support)
+ * What launchEntry would run, as built by the Rust launcher (see `LaunchPreview` in Rust)
  */
-async function launchBat(batPath: string): Promise<void> {
-  const command = Command.create("cmd", ["/c", batPath]);
-  await command.execute();
+export interface LaunchPreview {
+  entry_id: number;
+  profile: string | null;
+  program: string;
+  args: string[];
+  current_dir: string | null;
+  env: { name: string; value: string; previous: string | null }[];
+  pre_launch: HookPreview[];
+  start_steam: string[] | null;   // argv run first when Steam is not running
+  post_exit: HookPreview[];
+  tracked: boolean;
+  single_instance: boolean;
+}
+
+export interface HookPreview {
+  command: string;
+  timeout_secs: number;
+  program: string;
+  args: string[];
 }
 
 /**
- * Simple argument parser - splits by space but keeps quoted strings together
- * Example: '--arg1 "path with spaces" --arg2' => ['--arg1', 'path with spaces', '--arg2']
+ * Dry-run a launch with the named profile (or the default one) without starting anything.
+ * Rejects with a LaunchError if the launch would fail before spawning.
  */
-function parseArgs(argsString: string): string[] {
-  const args: string[] = [];
-  let current = "";
-  let inQuotes = false;
-  
-  for (const char of argsString) {
-    if (char === '"') {
-      inQuotes = !inQuotes;
-    } else if (char === " " && !inQuotes) {
-      if (current) {
-        args.push(current);
-        current = "";
-      }
-    } else {
-      current += char;
-    }
-  }
-  
-  if (current) {
-    args.push(current);
+export async function previewLaunch(id: number, profile: string | null = null): Promise<LaunchPreview> {
+  return invoke<LaunchPreview>("preview_launch", { id, profile });
+}
+
+/**
+ * Render a preview as plain text, one argv element per quoted item
+ */
+export function formatLaunchPreview(preview: LaunchPreview): string {
+  const argv = (program: string, args: string[]) =>
+    [program, ...args].map((a) => JSON.stringify(a)).join(" ");
+  const hooks = (label: string, list: HookPreview[]) =>
+    list.map((h) => `${label}: ${argv(h.program, h.args)} (timeout ${h.timeout_secs}s)`);
+
+  return [
+    `Profile: ${preview.profile ?? "(none)"}`,
+    `Command: ${argv(preview.program, preview.args)}`,
+    `Working dir: ${preview.current_dir ?? "(inherited)"}`,
+    ...preview.env.map((v) =>
+      v.previous === null ? `Env: ${v.name}=${v.value}` : `Env: ${v.name}=${v.value} (was ${v.previous})`
+    ),
+    ...hooks("Pre-launch", preview.pre_launch),
+    ...(preview.start_steam ? [`If Steam is closed: ${argv(preview.start_steam[0], preview.start_steam.slice(1))}`] : []),
+    ...hooks("Post-exit", preview.post_exit),
+    preview.tracked ? "Tracked until it exits" : "Not tracked (handed off to another program)",
+  ].join("\n");
+}
+
+// --- LAUNCH PROFILES ---
+
+/**
+ * A named launch variant of an entry (see `LaunchProfile` in Rust).
+ * Args replace the entry's, a non-empty working_dir replaces the entry's,
+ * and env is layered over the entry's.
+ */
+export interface LaunchProfile {
+  id: number | null;        // null for a profile that hasn't been saved yet
+  entry_id: number;
+  name: string;
+  launch_args: string;
+  working_dir: string;
+  env: Record<string, string>;
+  is_default: boolean;
+}
+
+/**
+ * Get the launch profiles of an entry, default first
+ */
+export async function getLaunchProfiles(entryId: number): Promise<LaunchProfile[]> {
+  return invoke<LaunchProfile[]>("list_launch_profiles", { entryId });
+}
+
+/**
+ * Create or update a launch profile, returning its id
+ */
+export async function saveLaunchProfile(profile: LaunchProfile): Promise<number> {
+  return invoke<number>("save_launch_profile", { profile });
+}
+
+/**
+ * Delete a launch profile
+ */
+export async function deleteLaunchProfile(id: number): Promise<void> {
+  await invoke("delete_launch_profile", { id });
+}
+
+/**
+ * Make a profile the entry's default, or pass null to launch the entry as stored
+ */
+export async function setDefaultLaunchProfile(entryId: number, profileId: number | null): Promise<void> {
+  await invoke("set_default_launch_profile", { entryId, profileId });
+}
+
+// --- PROCESS TRACKING ---
+
+/**
+ * A launched entry whose process is still alive.
+ */
+export interface RunningEntry {
+  entry_id: number;
+  pid: number;
+  started_at: number;   // unix seconds
+}
+
+/**
+ * Payload of the `entry-exited` event.
+ */
+export interface EntryExited {
+  entry_id: number;
+  pid: number;
+  exit_code: number | null;   // null when killed by a signal
+  duration_secs: number;
+}
+
+/**
+ * Get every tracked process that is currently running
+ */
+export async function getRunningEntries(): Promise<RunningEntry[]> {
+  return invoke<RunningEntry[]>("running_entries");
+}
+
+/**
+ * Subscribe to tracked processes exiting
+ */
+export async function onEntryExited(handler: (event: EntryExited) => void): Promise<UnlistenFn> {
+  return listen<EntryExited>("entry-exited", (e) => handler(e.payload));
+}
+
+/**
+ * What stop_entry / kill_entry did
+ */
+export interface StopReport {
+  entry_id: number;
+  pids: number[];
+  outcome: "not_running" | "exited" | "killed" | "still_running";
+}
+
+/**
+ * Close a running entry gracefully, force-killing it after a grace period
+ */
+export async function stopEntry(id: number): Promise<StopReport> {
+  return invoke<StopReport>("stop_entry", { id });
+}
+
+/**
+ * Force-kill a running entry right away
+ */
+export async function killEntry(id: number): Promise<StopReport> {
+  return invoke<StopReport>("kill_entry", { id });
+}
+
+// --- PLAYTIME ---
+
+/**
+ * Playtime totals for one entry, in seconds
+ */
+export interface Playtime {
+  entry_id: number;
+  total_secs: number;
+  last_7_days_secs: number;
+  last_session_secs: number | null;
+  last_played_at: number | null;   // unix seconds
+}
+
+/**
+ * Get playtime for a single entry
+ */
+export async function getPlaytime(id: number): Promise<Playtime> {
+  return invoke<Playtime>("entry_playtime", { id });
+}
+
+/**
+ * Get playtime for every entry that has been played, keyed by entry id
+ */
+export async function getLibraryPlaytime(): Promise<Record<number, Playtime>> {
+  const rows = await invoke<Playtime[]>("library_playtime");
+  return Object.fromEntries(rows.map((p) => [p.entry_id, p]));
+}
+
+/**
+ * Format seconds as a short duration
+ * Example: formatDuration(8100) => "2h 15m"
+ */
+export function formatDuration(secs: number): string {
+  const hours = Math.floor(secs / 3600);
+  const minutes = Math.floor((secs % 3600) / 60);
+  if (hours > 0) return `${hours}h ${minutes}m`;
+  if (minutes > 0) return `${minutes}m`;
+  return "<1m";
+}
+
+// --- LIBRARY HEALTH ---
+
+/**
+ * What the last health scan found for an entry (see `Health` in Rust)
+ */
+export type Health =
+  | { status: "ok" }
+  | { status: "missing"; path: string }
+  | { status: "not_executable"; path: string }
+  | { status: "not_installed"; app_id: string }
+  | { status: "invalid_url"; reason: string }
+  | { status: "unknown"; reason: string };
+
+export type EntryHealth = Health & {
+  entry_id: number;
+  checked_at: number;       // unix seconds
+  auto_deprecated: boolean; // deprecated was set by the scan, not by hand
+};
+
+const AUTO_DEPRECATE_KEY = "k_scope_auto_deprecate";
+
+export function getAutoDeprecate(): boolean {
+  return localStorage.getItem(AUTO_DEPRECATE_KEY) === "true";
+}
+
+export function setAutoDeprecate(enabled: boolean) {
+  localStorage.setItem(AUTO_DEPRECATE_KEY, String(enabled));
+}
+
+/**
+ * Check every entry's target on disk and store the results, keyed by entry id.
+ * With autoDeprecate, broken entries are also marked deprecated.
+ */
+export async function scanLibraryHealth(autoDeprecate = getAutoDeprecate()): Promise<Record<number, EntryHealth>> {
+  const rows = await invoke<EntryHealth[]>("scan_library_health", { autoDeprecate });
+  return Object.fromEntries(rows.map((h) => [h.entry_id, h]));
+}
+
+/**
+ * Results of the last scan, keyed by entry id
+ */
+export async function getLibraryHealth(): Promise<Record<number, EntryHealth>> {
+  const rows = await invoke<EntryHealth[]>("library_health");
+  return Object.fromEntries(rows.map((h) => [h.entry_id, h]));
+}
+
+/**
+ * A short explanation of a broken entry, or null if it looks fine
+ */
+export function healthMessage(health: Health | undefined): string | null {
+  switch (health?.status) {
+    case "missing":
+      return `${health.path} no longer exists.`;
+    case "not_executable":
+      return `${health.path} is not executable.`;
+    case "not_installed":
+      return `Steam app ${health.app_id} is not installed.`;
+    case "invalid_url":
+      return `Invalid URL: ${health.reason}.`;
+    default:
+      return null;
   }
-  
-  return args;
 }
 
 // --- FILE HELPERS ---
@@ -197,13 +485,109 @@ export async function pickExeFile(): Promise<string | null> {
 }
 
 /**
- * Opens file dialog to select a batch script
+ * Opens file dialog to select a launch script (.bat / .cmd on Windows, .sh on Linux)
  */
 export async function pickScriptFile(): Promise<string | null> {
   playSound("hover");
   const selected = await openDialog({
     title: "Select Launch Script",
-    filters: [{ name: "Batch Files", extensions: ["bat", "cmd"] }],
+    filters: [{ name: "Scripts", extensions: ["bat", "cmd", "sh"] }],
+    multiple: false,
+    directory: false,
+  });
+
+  if (typeof selected === "string") {
+    return selected;
+  }
+  return null;
+}
+
+/**
+ * Opens folder dialog to select a working directory
+ */
+export async function pickDirectory(): Promise<string | null> {
+  playSound("hover");
+  const selected = await openDialog({
+    title: "Select Working Directory",
+    multiple: false,
+    directory: true,
+  });
+
+  if (typeof selected === "string") {
+    return selected;
+  }
+  return null;
+}
+
+/**
+ * An entry read from a shortcut file (see `ImportedEntry` in Rust)
+ */
+export interface ImportedEntry {
+  name: string;
+  launch_type: LaunchType;
+  launch_data: string;
+  launch_args: string;
+  working_dir: string;        // "" = folder of the target
+  icon_path: string | null;   // may be an .ico / .exe / .dll the UI cannot show
+  image_hash: string | null;  // set when the icon is a plain image, now in the image store
+}
+
+/**
+ * Opens file dialog to select a Windows (.lnk) or Linux (.desktop) shortcut
+ */
+export async function pickShortcutFile(): Promise<string | null> {
+  playSound("hover");
+  const selected = await openDialog({
+    title: "Import Shortcut",
+    filters: [{ name: "Shortcuts", extensions: ["lnk", "desktop"] }],
+    multiple: false,
+    directory: false,
+  });
+
+  if (typeof selected === "string") {
+    return selected;
+  }
+  return null;
+}
+
+/**
+ * Read a shortcut file into entry fields. Rejects with a message if it cannot be parsed.
+ */
+export async function importShortcut(path: string): Promise<ImportedEntry> {
+  return invoke<ImportedEntry>("import_shortcut", { path });
+}
+
+/**
+ * Opens save dialog to pick where a library export goes. The extension picks the format.
+ */
+export async function pickLibraryExportFile(): Promise<{ path: string; format: ExportFormat } | null> {
+  playSound("hover");
+  const selected = await saveDialog({
+    title: "Export Library",
+    defaultPath: "k-scope-library.zip",
+    filters: [
+      { name: "Zip with images", extensions: ["zip"] },
+      { name: "JSON", extensions: ["json"] },
+      { name: "TOML", extensions: ["toml"] },
+    ],
+  });
+
+  if (typeof selected !== "string") {
+    return null;
+  }
+  const extension = selected.split(".").pop()?.toLowerCase();
+  const format: ExportFormat = extension === "json" || extension === "toml" ? extension : "zip";
+  return { path: selected, format };
+}
+
+/**
+ * Opens file dialog to select a library export
+ */
+export async function pickLibraryImportFile(): Promise<string | null> {
+  playSound("hover");
+  const selected = await openDialog({
+    title: "Import Library",
+    filters: [{ name: "Library", extensions: ["zip", "json", "toml"] }],
     multiple: false,
     directory: false,
   });
@@ -214,8 +598,40 @@ export async function pickScriptFile(): Promise<string | null> {
   return null;
 }
 
+const PORTABLE_PATHS_KEY = "k_scope_portable_paths";
+
+/**
+ * Whether picked paths are stored in portable form (see `portablePath`)
+ */
+export function getPortablePaths(): boolean {
+  return localStorage.getItem(PORTABLE_PATHS_KEY) === "true";
+}
+
+export function setPortablePaths(enabled: boolean) {
+  localStorage.setItem(PORTABLE_PATHS_KEY, String(enabled));
+}
+
 /**
- * Opens file dialog to select an image, converts to Base64
+ * Rewrite a picked path so it works for other accounts: the home folder becomes ~,
+ * and literal % and $ are doubled so the launcher does not expand them.
+ * Example: "/home/me/Games/doom" => "~/Games/doom"
+ */
+export async function portablePath(path: string): Promise<string> {
+  return invoke<string>("portable_path", { path });
+}
+
+/**
+ * Escape a picked path as written: literal % and $ are doubled and a leading ~ becomes ~~,
+ * so the launcher does not expand them.
+ * Example: "/opt/$game/100%" => "/opt/$$game/100%%"
+ */
+export async function literalPath(path: string): Promise<string> {
+  return invoke<string>("literal_path", { path });
+}
+
+/**
+ * Opens file dialog to select an image and copies it into the image store.
+ * Returns the image's hash, or null if nothing was picked.
  */
 export async function pickImageFile(): Promise<string | null> {
   playSound("hover");
@@ -223,7 +639,7 @@ export async function pickImageFile(): Promise<string | null> {
   const selected = await openDialog({
     title: "Select Image",
     filters: [
-      { name: "Images", extensions: ["png", "jpg", "jpeg", "webp", "gif"] },
+      { name: "Images", extensions: ["png", "jpg", "jpeg", "webp", "gif", "bmp"] },
     ],
     multiple: false,
     directory: false,
@@ -232,29 +648,58 @@ export async function pickImageFile(): Promise<string | null> {
   if (typeof selected !== "string") {
     return null;
   }
+  return invoke<string>("store_image", { path: selected });
+}
 
-  try {
-    const bytes = await readFile(selected);
+let imageDir = "";
 
-    let binary = "";
-    const len = bytes.byteLength;
-    for (let i = 0; i < len; i++) {
-      binary += String.fromCharCode(bytes[i]);
-    }
+/**
+ * Look up the image store folder. Call once before rendering any entry art.
+ */
+export async function loadImageDir(): Promise<void> {
+  imageDir = await invoke<string>("image_dir");
+}
 
-    const base64 = btoa(binary);
+/**
+ * URL the webview can load a stored image or thumbnail from, served by the asset protocol
+ */
+export function imageUrl(name: string): string {
+  return name ? convertFileSrc(imageDir + name) : "";
+}
 
-    const ext = selected.split(".").pop()?.toLowerCase() || "png";
-    let mimeType = "image/png";
-    if (ext === "jpg" || ext === "jpeg") mimeType = "image/jpeg";
-    if (ext === "webp") mimeType = "image/webp";
-    if (ext === "gif") mimeType = "image/gif";
+export type ThumbnailKind = "grid" | "list";
 
-    return `data:${mimeType};base64,${base64}`;
-  } catch (error) {
-    console.error("Image read failed:", error);
-    return null;
-  }
+/**
+ * Thumbnail size and fit for one kind of card (see `ThumbnailSpec` in Rust)
+ */
+export interface ThumbnailSpec {
+  width: number;
+  height: number;
+  fit: "crop" | "pad";
+}
+
+export interface ThumbnailSettings {
+  grid: ThumbnailSpec;  // game covers
+  list: ThumbnailSpec;  // app icons
+}
+
+/**
+ * Thumbnail file names by image hash, rendered on first use.
+ * Images without one are missing from the result; show the original instead.
+ */
+export async function getThumbnails(kind: ThumbnailKind, hashes: string[]): Promise<Record<string, string>> {
+  return invoke<Record<string, string>>("thumbnails", { kind, hashes });
+}
+
+export async function getThumbnailSettings(): Promise<ThumbnailSettings> {
+  return invoke<ThumbnailSettings>("thumbnail_settings");
+}
+
+/**
+ * Save thumbnail sizes; thumbnails are rendered again under the new ones
+ */
+export async function saveThumbnailSettings(settings: ThumbnailSettings): Promise<void> {
+  await invoke("save_thumbnail_settings", { settings });
 }
 
 /**
@@ -263,7 +708,201 @@ export async function pickImageFile(): Promise<string | null> {
 export function getFilenameFromPath(path: string): string {
   const parts = path.split(/[/\\]/);
   const filename = parts[parts.length - 1] || "";
-  return filename.replace(/\.(exe|bat|cmd)$/i, "");
+  return filename.replace(/\.(exe|bat|cmd|sh)$/i, "");
+}
+
+/**
+ * Turn an entry's env into editable `KEY=value` lines
+ */
+export function envToText(env: Record<string, string>): string {
+  return Object.entries(env).map(([k, v]) => `${k}=${v}`).join("\n");
+}
+
+/**
+ * Parse `KEY=value` lines into an env map.
+ * Blank lines and lines starting with # are ignored.
+ * Example: 'DXVK_HUD=fps\nWINEDEBUG=-all' => { DXVK_HUD: "fps", WINEDEBUG: "-all" }
+ */
+export function envFromText(text: string): { env: Record<string, string> } | { error: string } {
+  const vars: Record<string, string> = {};
+  for (const raw of text.split("\n")) {
+    const line = raw.trim();
+    if (!line || line.startsWith("#")) continue;
+
+    const eq = line.indexOf("=");
+    const key = eq > 0 ? line.slice(0, eq).trim() : "";
+    if (!/^[A-Za-z_][A-Za-z0-9_]*$/.test(key)) {
+      return { error: `Invalid environment line: "${line}". Use KEY=value.` };
+    }
+    vars[key] = line.slice(eq + 1);
+  }
+  return { env: vars };
+}
+
+/**
+ * A command run before launch or after exit (see `Hook` in Rust)
+ */
+export interface Hook {
+  command: string;
+  timeout_secs: number;
+}
+
+export interface Hooks {
+  pre_launch: Hook[];
+  post_exit: Hook[];
+}
+
+export const DEFAULT_HOOK_TIMEOUT_SECS = 30;
+
+/**
+ * Build an entry's hooks from one-command-per-line text.
+ * Every line is checked with the launcher's tokenizer.
+ */
+export async function hooksFromText(
+  preLaunch: string,
+  postExit: string,
+  timeoutSecs: number
+): Promise<{ hooks: Hooks } | { error: string }> {
+  const toHooks = async (text: string, label: string): Promise<Hook[] | string> => {
+    const hooks: Hook[] = [];
+    for (const raw of text.split("\n")) {
+      const command = raw.trim();
+      if (!command) continue;
+      const error = await validateLaunchArgs(command);
+      if (error) return `${label} hook "${command}": ${error}`;
+      hooks.push({ command, timeout_secs: timeoutSecs });
+    }
+    return hooks;
+  };
+
+  const pre_launch = await toHooks(preLaunch, "Pre-launch");
+  if (typeof pre_launch === "string") return { error: pre_launch };
+  const post_exit = await toHooks(postExit, "Post-exit");
+  if (typeof post_exit === "string") return { error: post_exit };
+
+  return { hooks: { pre_launch, post_exit } };
+}
+
+/**
+ * A compatibility layer or wrapper an entry starts through (see `Runner` in Rust)
+ */
+export interface Runner {
+  command: string;              // e.g. "wine", "gamemoderun", "/path/proton run"
+  env: Record<string, string>;  // e.g. { WINEPREFIX: "/home/me/.wine-games" }
+}
+
+/**
+ * Render a runner chain as one line per runner, outermost first.
+ * Env vars are written shell-style in front of the command.
+ * Example: [{ command: "wine", env: { WINEPREFIX: "/pfx" } }] => "WINEPREFIX=/pfx wine"
+ */
+export function runnersToText(runners: Runner[]): string {
+  return runners
+    .map((r) => {
+      const env = Object.entries(r.env).map(([k, v]) => (/\s/.test(v) ? `${k}="${v}"` : `${k}=${v}`));
+      return [...env, r.command].join(" ");
+    })
+    .join("\n");
+}
+
+/**
+ * Parse one runner per line, outermost first, e.g. "gamemoderun" then "WINEPREFIX=/pfx wine".
+ * Every command is checked with the launcher's tokenizer.
+ */
+export async function runnersFromText(text: string): Promise<{ runners: Runner[] } | { error: string }> {
+  const runners: Runner[] = [];
+  for (const raw of text.split("\n")) {
+    let line = raw.trim();
+    if (!line || line.startsWith("#")) continue;
+
+    const env: Record<string, string> = {};
+    let m: RegExpMatchArray | null;
+    while ((m = line.match(/^([A-Za-z_][A-Za-z0-9_]*)=("([^"]*)"|\S*)\s*/))) {
+      env[m[1]] = m[3] ?? m[2];
+      line = line.slice(m[0].length);
+    }
+    if (!line) return { error: `Runner "${raw.trim()}" has no command.` };
+
+    const error = await validateLaunchArgs(line);
+    if (error) return { error: `Runner "${line}": ${error}` };
+    runners.push({ command: line, env });
+  }
+  return { runners };
+}
+
+/**
+ * Get the runner chain used by entries without their own
+ */
+export async function getGlobalRunners(): Promise<Runner[]> {
+  return invoke<Runner[]>("global_runners");
+}
+
+/**
+ * Replace the global runner chain. Rejects with a LaunchError for an invalid runner.
+ */
+export async function saveGlobalRunners(runners: Runner[]): Promise<void> {
+  return invoke("save_global_runners", { runners });
+}
+
+export type MacroMode = "sequential" | "parallel";
+export type FailurePolicy = "stop" | "continue";
+
+export interface MacroStep {
+  entry_id: number;
+  profile: string | null;  // null uses the entry's default
+  delay_ms: number;        // sequential: after the previous step; parallel: after the macro starts
+}
+
+/**
+ * What a macro entry runs (see `Macro` in Rust); its name, category and art are on the entry
+ */
+export interface Macro {
+  mode: MacroMode;
+  on_failure: FailurePolicy;  // "stop" skips every step that has not started yet
+  steps: MacroStep[];
+}
+
+export type StepOutcome =
+  | { status: "launched"; pid: number }
+  | { status: "already_running"; pid: number }
+  | { status: "failed"; error: LaunchError }
+  | { status: "skipped" };
+
+export type StepReport = { entry_id: number } & StepOutcome;
+
+/**
+ * Get the steps of a macro entry, for the edit form
+ */
+export async function getMacro(entryId: number): Promise<Macro> {
+  return invoke<Macro>("get_macro", { entryId });
+}
+
+/**
+ * True if no step of a macro run failed or was skipped
+ */
+export function macroSucceeded(steps: StepReport[]): boolean {
+  return steps.every((step) => step.status === "launched" || step.status === "already_running");
+}
+
+/**
+ * One line per step of a macro run, e.g. "Portal: failed (File not found: ...)"
+ */
+export function formatMacroReport(steps: StepReport[], nameOf: (id: number) => string): string {
+  return steps
+    .map((step) => {
+      const name = nameOf(step.entry_id);
+      switch (step.status) {
+        case "launched":
+          return `${name}: launched (PID ${step.pid})`;
+        case "already_running":
+          return `${name}: already running (PID ${step.pid})`;
+        case "failed":
+          return `${name}: failed (${launchErrorMessage(step.error)})`;
+        case "skipped":
+          return `${name}: skipped`;
+      }
+    })
+    .join("\n");
 }
 
 /**
@@ -274,7 +913,8 @@ export function getLaunchTypeLabel(type: LaunchType): string {
     steam: "Steam",
     exe: "Executable",
     url: "URL",
-    bat: "Batch Script"
+    bat: "Batch Script",
+    macro: "Macro"
   };
   return labels[type];
 }
\ No newline at end of file
@@ -71,7 +71,7 @@ let games: Game[] = [
   {
     name: "Duet Night Abyss",
     batPath:
-      "C:/Users/arman/Documents/Projects/k-scope/static/scripts/launch_dna.bat",
+      "~/Documents/Projects/k-scope/static/scripts/launch_dna.bat",
     image: "/images/dna.jpg",
   },
 ];
@@ -80,13 +80,13 @@ let apps: App[] = [
   {
     name: "Wiztree",
     batPath:
-      "C:/Users/arman/Documents/Projects/k-scope/static/scripts/launch_wiztree.bat",
+      "~/Documents/Projects/k-scope/static/scripts/launch_wiztree.bat",
     icon: "/icons/wiztree.png",
   },
   {
     name: "Prism",
     batPath:
-      "C:/Users/arman/Documents/Projects/k-scope/static/scripts/launch_prism.bat",
+      "~/Documents/Projects/k-scope/static/scripts/launch_prism.bat",
     icon: "/icons/prism.png",
   },
 ];
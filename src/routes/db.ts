@@ -1,189 +1,206 @@
 // =============================================================================
 // K-Scope Database Module
-// SQLite wrapper with flexible launch type support
+// Typed access to the library, stored and validated by the Rust side
 // =============================================================================
 
-import Database from "@tauri-apps/plugin-sql";
+import { invoke } from "@tauri-apps/api/core";
+import type { Hooks, Macro, Runner } from "../lib/logic";
 
-// Launch types supported by the app
-export type LaunchType = "steam" | "exe" | "url" | "bat";
+// Launch types supported by the app; a macro runs other entries
+export type LaunchType = "steam" | "exe" | "url" | "bat" | "macro";
 
-export interface Entry {
+// How a category shows its entries: cover cards or compact icons.
+// Matches the thumbnail kind rendered for them.
+export type DisplayMode = "grid" | "list";
+
+// The order of a category's entries
+export type EntrySort = "name" | "recently_added" | "recently_played" | "playtime";
+
+// What the category editor saves (see `CategoryFields` in Rust)
+export interface CategoryFields {
+  name: string;
+  display_mode: DisplayMode;
+  entry_sort: EntrySort;
+}
+
+// A launcher tab; the library starts with "Games" and "Apps"
+export interface Category extends CategoryFields {
   id: number;
-  type: "game" | "app";
+  sort_order: number;
+}
+
+// What the add / edit form saves (see `EntryFields` in Rust)
+export interface EntryFields {
   name: string;
   launch_type: LaunchType;
-  launch_data: string;      // Steam ID, exe path, URL, or bat path
-  launch_args: string;      // Optional args (mainly for exe)
-  image_data: string;       // base64 encoded image
+  launch_data: string;      // Steam ID, exe path, URL, or bat path; empty for macros
+  launch_args: string;      // Optional args (exe, or Steam launch options)
+  working_dir: string;      // Empty = folder of the exe / script
+  env: Record<string, string>; // Environment overrides
+  hooks: Hooks;
+  single_instance: boolean; // Don't start a second copy while one is running
+  start_steam_silently: boolean; // Steam: start the client in the tray before launching
+  runners: Runner[] | null; // Runners wrapping exe / bat; null = global runners
+  image_hash: string;       // cover art / icon in the image store; empty for none
+}
+
+export interface Entry extends EntryFields {
+  id: number;
+  uuid: string;             // stable across machines, used to match entries on import
+  category_id: number;
   deprecated: boolean;
   created_at: number;
 }
 
-let db: Database | null = null;
+/**
+ * Get the entries of one category, or of all in tab order, each in the
+ * order its category's `entry_sort` picks
+ */
+export async function getEntries(categoryId?: number): Promise<Entry[]> {
+  return await invoke<Entry[]>("list_entries", { categoryId: categoryId ?? null });
+}
 
 /**
- * Initialize the database connection and create/migrate tables
+ * Get one entry by ID
  */
-export async function initDatabase(): Promise<void> {
-  db = await Database.load("sqlite:kscope.db");
-
-  // Check if we need to migrate from old schema
-  const tableInfo = await db.select<{ name: string }[]>(
-    "PRAGMA table_info(entries)"
-  );
-  
-  const columns = tableInfo.map(col => col.name);
-  const hasOldSchema = columns.includes("bat_path") && !columns.includes("launch_type");
-  const tableExists = columns.length > 0;
-
-  if (hasOldSchema) {
-    // Migrate from old schema
-    console.log("Migrating database to new schema...");
-    
-    // 1. Rename old table
-    await db.execute("ALTER TABLE entries RENAME TO entries_old");
-    
-    // 2. Create new table
-    await db.execute(`
-      CREATE TABLE entries (
-        id INTEGER PRIMARY KEY AUTOINCREMENT,
-        type TEXT NOT NULL CHECK(type IN ('game', 'app')),
-        name TEXT NOT NULL,
-        launch_type TEXT NOT NULL DEFAULT 'bat' CHECK(launch_type IN ('steam', 'exe', 'url', 'bat')),
-        launch_data TEXT NOT NULL,
-        launch_args TEXT DEFAULT '',
-        image_data TEXT NOT NULL,
-        deprecated INTEGER DEFAULT 0,
-        created_at INTEGER DEFAULT (unixepoch())
-      )
-    `);
-    
-    // 3. Copy data, converting bat_path to launch_data with type 'bat'
-    await db.execute(`
-      INSERT INTO entries (id, type, name, launch_type, launch_data, launch_args, image_data, deprecated, created_at)
-      SELECT id, type, name, 'bat', bat_path, '', image_data, deprecated, created_at
-      FROM entries_old
-    `);
-    
-    // 4. Drop old table
-    await db.execute("DROP TABLE entries_old");
-    
-    console.log("Migration complete!");
-    
-  } else if (!tableExists) {
-    // Fresh install - create new schema
-    await db.execute(`
-      CREATE TABLE entries (
-        id INTEGER PRIMARY KEY AUTOINCREMENT,
-        type TEXT NOT NULL CHECK(type IN ('game', 'app')),
-        name TEXT NOT NULL,
-        launch_type TEXT NOT NULL DEFAULT 'bat' CHECK(launch_type IN ('steam', 'exe', 'url', 'bat')),
-        launch_data TEXT NOT NULL,
-        launch_args TEXT DEFAULT '',
-        image_data TEXT NOT NULL,
-        deprecated INTEGER DEFAULT 0,
-        created_at INTEGER DEFAULT (unixepoch())
-      )
-    `);
-  }
-
-  console.log("Database initialized");
+export async function getEntry(id: number): Promise<Entry> {
+  return await invoke<Entry>("get_entry", { id });
 }
 
 /**
- * Get all entries of a specific type
+ * Add a new entry. A macro entry needs its definition.
+ * Rejects with a message if a field is invalid.
  */
-export async function getEntries(type: "game" | "app"): Promise<Entry[]> {
-  if (!db) throw new Error("Database not initialized");
+export async function addEntry(
+  categoryId: number,
+  entry: EntryFields,
+  definition: Macro | null = null
+): Promise<Entry> {
+  return await invoke<Entry>("create_entry", { categoryId, entry, definition });
+}
 
-  const rows = await db.select<Entry[]>(
-    "SELECT * FROM entries WHERE type = ? ORDER BY created_at DESC",
-    [type]
-  );
+/**
+ * Update an entry and move it to a category. A macro entry needs its definition.
+ * Rejects with a message if a field is invalid.
+ */
+export async function updateEntry(
+  id: number,
+  categoryId: number,
+  entry: EntryFields,
+  definition: Macro | null = null
+): Promise<Entry> {
+  return await invoke<Entry>("update_entry", { id, categoryId, entry, definition });
+}
 
-  return rows.map((row) => ({
-    ...row,
-    deprecated: Boolean(row.deprecated),
-  }));
+/**
+ * Delete an entry by ID
+ */
+export async function deleteEntry(id: number): Promise<void> {
+  await invoke("delete_entry", { id });
 }
 
+// --- CATEGORIES ---
+
 /**
- * Get all games
+ * Get all categories in tab order
  */
-export async function getGames(): Promise<Entry[]> {
-  return getEntries("game");
+export async function getCategories(): Promise<Category[]> {
+  return await invoke<Category[]>("list_categories");
 }
 
 /**
- * Get all apps
+ * Add a category after the existing ones. Rejects if the name is empty or taken.
  */
-export async function getApps(): Promise<Entry[]> {
-  return getEntries("app");
+export async function addCategory(category: CategoryFields): Promise<Category> {
+  return await invoke<Category>("create_category", { category });
 }
 
 /**
- * Add a new entry
+ * Rename a category or change its display mode
  */
-export async function addEntry(
-  type: "game" | "app",
-  name: string,
-  launchType: LaunchType,
-  launchData: string,
-  launchArgs: string,
-  imageData: string
-): Promise<Entry> {
-  if (!db) throw new Error("Database not initialized");
+export async function updateCategory(id: number, category: CategoryFields): Promise<Category> {
+  return await invoke<Category>("update_category", { id, category });
+}
 
-  const result = await db.execute(
-    "INSERT INTO entries (type, name, launch_type, launch_data, launch_args, image_data) VALUES (?, ?, ?, ?, ?, ?)",
-    [type, name, launchType, launchData, launchArgs, imageData]
-  );
+/**
+ * Delete an empty category. Rejects if it still has entries or is the last one.
+ */
+export async function deleteCategory(id: number): Promise<void> {
+  await invoke("delete_category", { id });
+}
+
+/**
+ * Save the tab order, given as every category id from first to last
+ */
+export async function reorderCategories(ids: number[]): Promise<void> {
+  await invoke("reorder_categories", { ids });
+}
+
+// --- EXPORT / IMPORT ---
 
-  const entries = await db.select<Entry[]>(
-    "SELECT * FROM entries WHERE id = ?",
-    [result.lastInsertId]
-  );
+export type ExportFormat = "json" | "toml" | "zip";
 
-  return {
-    ...entries[0],
-    deprecated: Boolean(entries[0].deprecated),
-  };
+// "merge" keeps entries missing from the file, "replace" removes them
+export type ImportMode = "merge" | "replace";
+
+export interface ImportReport {
+  added: number;
+  updated: number;
+  removed: number;
 }
 
 /**
- * Delete an entry by ID
+ * Write the whole library (entries, profiles and art) to a file
  */
-export async function deleteEntry(id: number): Promise<void> {
-  if (!db) throw new Error("Database not initialized");
-  await db.execute("DELETE FROM entries WHERE id = ?", [id]);
+export async function exportLibrary(path: string, format: ExportFormat): Promise<void> {
+  await invoke("export_library", { path, format });
 }
 
 /**
- * Toggle deprecated status
+ * Read a file written by exportLibrary. Rejects with a message, changing nothing, if it is invalid.
  */
-export async function toggleDeprecated(id: number): Promise<void> {
-  if (!db) throw new Error("Database not initialized");
-  await db.execute(
-    "UPDATE entries SET deprecated = NOT deprecated WHERE id = ?",
-    [id]
-  );
+export async function importLibrary(path: string, mode: ImportMode): Promise<ImportReport> {
+  return await invoke<ImportReport>("import_library", { path, mode });
+}
+
+// --- BACKUPS ---
+
+// A snapshot of the library database (see `Backup` in Rust)
+export interface Backup {
+  id: string;
+  reason: string;     // what it was taken before, e.g. "startup", "edit-entry" or "delete-entry"
+  created_at: number; // unix seconds
+  size: number;       // bytes
+}
+
+// How many snapshots to keep, and for how long; the newest is always kept.
+// Startup snapshots and the ones taken before changes each get max_count.
+export interface BackupSettings {
+  max_count: number;
+  max_age_days: number;
 }
 
 /**
- * Update an entry
+ * Get all snapshots, newest first
  */
-export async function updateEntry(
-  id: number,
-  name: string,
-  launchType: LaunchType,
-  launchData: string,
-  launchArgs: string,
-  imageData: string
-): Promise<void> {
-  if (!db) throw new Error("Database not initialized");
-  await db.execute(
-    "UPDATE entries SET name = ?, launch_type = ?, launch_data = ?, launch_args = ?, image_data = ? WHERE id = ?",
-    [name, launchType, launchData, launchArgs, imageData, id]
-  );
-}
\ No newline at end of file
+export async function listBackups(): Promise<Backup[]> {
+  return await invoke<Backup[]>("list_backups");
+}
+
+/**
+ * Replace the library with a snapshot. The current library is backed up first.
+ */
+export async function restoreBackup(id: string): Promise<void> {
+  await invoke("restore_backup", { id });
+}
+
+export async function getBackupSettings(): Promise<BackupSettings> {
+  return await invoke<BackupSettings>("backup_settings");
+}
+
+/**
+ * Save retention rules. Snapshots they no longer cover are removed right away.
+ */
+export async function saveBackupSettings(settings: BackupSettings): Promise<void> {
+  await invoke("save_backup_settings", { settings });
+}